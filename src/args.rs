@@ -1,36 +1,547 @@
-use clap::{arg, command, Parser};
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::{
+    error::ErrorKind, parser::ValueSource, Args as _, CommandFactory, FromArgMatches, Parser,
+    Subcommand, ValueEnum,
+};
+use clap_complete::Shell;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Url,
+};
+
+use crate::ui::theme::Theme;
 
 pub(crate) fn parse() -> Args {
-    Args::parse()
+    try_parse_from(env::args_os()).unwrap_or_else(|e| e.exit())
+}
+
+// Parse like `Args::try_parse_from`, but reject arguments of the default `download` subcommand
+// given along with another subcommand, rather than silently ignoring them.
+fn try_parse_from<I, T>(args: I) -> Result<Args, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut command = Args::command();
+    let matches = command.try_get_matches_from_mut(args)?;
+
+    if let Some((subcommand, _)) = matches.subcommand() {
+        let download_command = DownloadArgs::augment_args(clap::Command::new("download"));
+        let conflicting = download_command
+            .get_arguments()
+            .find(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+            .map(|arg| {
+                arg.get_long()
+                    .map_or_else(|| arg.get_id().to_string(), |long| format!("--{long}"))
+            });
+        if let Some(arg) = conflicting {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                format!("the argument '{arg}' cannot be used with the '{subcommand}' subcommand"),
+            ));
+        }
+    }
+
+    Args::from_arg_matches(&matches)
 }
 
 #[derive(Debug, Parser)]
-#[command(author, version, about)]
+#[command(
+    author,
+    version,
+    about,
+    propagate_version = true,
+    // Without a subcommand, the arguments of the default `download` subcommand are accepted.
+    subcommand_negates_reqs = true
+)]
 pub(crate) struct Args {
+    /// Print a shell completion script to stdout, then exit
+    #[arg(long, value_enum, exclusive = true)]
+    pub(crate) generate_completions: Option<Shell>,
+
+    #[command(flatten)]
+    pub(crate) global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    #[command(flatten)]
+    pub(crate) download: DownloadArgs,
+}
+
+impl Args {
+    // Split into global arguments and the subcommand, which defaults to `download`.
+    pub(crate) fn into_parts(self) -> (GlobalArgs, Command) {
+        (
+            self.global,
+            self.command.unwrap_or(Command::Download(self.download)),
+        )
+    }
+}
+
+// Print a completion script for the given shell, covering all subcommands and arguments.
+pub(crate) fn print_completions(shell: Shell) -> io::Result<()> {
+    write_completions(shell, &mut io::stdout().lock())
+}
+
+// The script is generated in full before writing, as generating panics on write errors.
+// A closed reader, e.g. `head`, is not an error: It has read all it wanted.
+fn write_completions(shell: Shell, out: &mut impl Write) -> io::Result<()> {
+    let mut command = Args::command();
+    let bin_name = command.get_name().to_owned();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, bin_name, &mut script);
+
+    match out.write_all(&script).and_then(|()| out.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+#[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)] // Parsed only once.
+pub(crate) enum Command {
+    /// Download all videos from the given URLs (default)
+    Download(DownloadArgs),
+
+    /// List all videos extracted from the given URLs, without downloading them
+    List(ListArgs),
+}
+
+// Arguments shared by all subcommands.
+#[derive(Debug, clap::Args)]
+#[allow(clippy::struct_excessive_bools)] // Command line flags are naturally independent bools.
+pub(crate) struct GlobalArgs {
     /// Path to the downloader, such as `yt-dlp` or `youtube-dl`
-    #[arg(long, default_value_t = String::from("yt-dlp"))]
+    #[arg(long, global = true, env = "SHOWCASE_DL_DOWNLOADER", default_value_t = String::from("yt-dlp"))]
     pub(crate) downloader: String,
 
+    /// Timeout in seconds for HTTP requests made while extracting videos
+    #[arg(long, global = true, default_value_t = crate::util::DEFAULT_HTTP_TIMEOUT_SECS, value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) http_timeout_secs: u64,
+
+    /// Only download embeds whose URL contains this string, skipping unrelated embeds on the page, such as ads
+    #[arg(long, global = true)]
+    pub(crate) url_contains: Option<String>,
+
     /// Export OTLP traces - run a trace collector such as jaeger when using this option
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub(crate) otlp_export: bool,
 
     /// Referer URL - use if passing the URL of a Vimeo showcase or simple player with referer restriction, rather than a page containing embeds
-    #[arg(long)]
+    #[arg(long, global = true, env = "SHOWCASE_DL_REFERER", value_parser = parse_referer)]
     pub(crate) referer: Option<String>,
 
-    /// UI refresh interval in milliseconds
-    #[arg(short, long, default_value_t = 25)]
-    pub(crate) tick: u64,
+    /// Derive the referer of a player URL from the player URL's own origin - ignored if --referer is given
+    #[arg(long, global = true)]
+    pub(crate) referer_from_url: bool,
+
+    /// Refer to showcase clips by their showcase's URL, rather than by the embedding page or --referer
+    #[arg(long, global = true)]
+    pub(crate) referer_from_showcase: bool,
+
+    /// Additional HTTP header, as `Name: Value`, sent with all requests and passed to the downloader - may be given multiple times
+    #[arg(long = "header", global = true, value_name = "NAME: VALUE", value_parser = parse_header)]
+    pub(crate) headers: Vec<(String, String)>,
+
+    /// Only download the first N videos discovered, in source order, e.g. to sample a large showcase
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) limit: Option<u64>,
+
+    /// Password for password protected Vimeo showcases, events and videos
+    #[arg(long, global = true)]
+    pub(crate) video_password: Option<String>,
+
+    /// Do not write the log file `showcase-dl.log` - OTLP export keeps the verbosity level
+    #[arg(long, global = true)]
+    pub(crate) no_log_file: bool,
 
     #[command(flatten)]
     pub(crate) verbosity: clap_verbosity_flag::Verbosity,
+}
+
+#[derive(Debug, clap::Args)]
+#[allow(clippy::struct_excessive_bools)] // Command line flags are naturally independent bools.
+pub(crate) struct DownloadArgs {
+    /// Exit automatically once all downloads have finished or failed, rather than waiting for the user to quit
+    #[arg(long)]
+    pub(crate) exit_on_done: bool,
+
+    /// Print a line per video stage change, rather than showing the terminal user interface - implied if stdout is not a terminal
+    #[arg(long)]
+    pub(crate) no_tui: bool,
+
+    /// Send a desktop notification once all downloads have finished
+    #[arg(long)]
+    pub(crate) notify: bool,
+
+    /// Webhook URL - a JSON summary of all downloads is posted to this URL once all downloads have finished
+    #[arg(long)]
+    pub(crate) webhook_url: Option<Url>,
+
+    /// Maximum download rate per video, in the downloader's rate syntax, e.g. `50K` or `4.2M`
+    #[arg(long)]
+    pub(crate) limit_rate: Option<String>,
+
+    /// Skip videos larger than this size, in the downloader's size syntax, e.g. `50K` or `4.2G`
+    #[arg(long)]
+    pub(crate) max_filesize: Option<String>,
+
+    /// Output file name template, in the downloader's template syntax, e.g. `%(uploader)s/%(title)s.%(ext)s`
+    #[arg(long)]
+    pub(crate) output_template: Option<String>,
+
+    /// Restrict output file names to characters valid on Windows, e.g. when downloading to a network share mounted on Windows
+    #[arg(long)]
+    pub(crate) windows_filenames: bool,
+
+    /// Embed metadata, such as title, uploader and description, into each output file
+    #[arg(long, alias = "add-metadata")]
+    pub(crate) embed_metadata: bool,
+
+    /// Embed each video's thumbnail into its output file as cover art
+    #[arg(long)]
+    pub(crate) embed_thumbnail: bool,
+
+    /// Download subtitles in all available languages alongside each video
+    #[arg(long = "include-subtitles")]
+    pub(crate) subtitles: bool,
+
+    /// Also download automatically generated subtitles, where the platform offers them
+    #[arg(long = "include-auto-subtitles", requires = "subtitles")]
+    pub(crate) auto_subtitles: bool,
+
+    /// Let the downloader resolve each video and its formats without downloading, to validate referer, cookies and format options before a real run
+    #[arg(long)]
+    pub(crate) simulate: bool,
+
+    /// Limit the length of output file names to this number of characters, excluding the extension
+    #[arg(long)]
+    pub(crate) trim_filenames: Option<usize>,
+
+    /// Restart partially downloaded files, e.g. left by a previous run, rather than resuming them
+    #[arg(long)]
+    pub(crate) no_continue: bool,
+
+    /// Number of fragments of each fragmented download, such as HLS or DASH, which the downloader fetches concurrently - multiplies with --max-concurrent-downloads
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) concurrent_fragments: Option<u16>,
 
-    /// URL - Either the target page, containing Vimeo showcase embeds, or a Vimeo showcase URL (with --referer)
-    #[arg()]
-    pub(crate) url: String,
+    /// Maximum number of concurrent downloads - further videos are queued until a download finishes
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) max_concurrent_downloads: Option<u16>,
+
+    /// Minimum number of seconds to sleep between starting downloads, to avoid being rate limited on large showcases
+    #[arg(long, value_name = "SECS", value_parser = parse_secs)]
+    pub(crate) min_sleep_interval: Option<Duration>,
+
+    /// Maximum number of seconds to sleep between starting downloads - the sleep is randomized between minimum and maximum
+    #[arg(long, value_name = "SECS", value_parser = parse_secs, requires = "min_sleep_interval")]
+    pub(crate) max_sleep_interval: Option<Duration>,
+
+    /// Time to sleep between the downloader's own retries, e.g. of fragments, in the downloader's syntax, e.g. `5` or `fragment:exp=1:20`
+    #[arg(long)]
+    pub(crate) retry_sleep: Option<String>,
+
+    /// Number of attempts at downloading each video - failed downloads are retried until all attempts are used up
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..))]
+    pub(crate) download_attempts: u8,
+
+    /// Signal sent to running downloads on shutdown
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) shutdown_signal: ShutdownSignalArg,
+
+    /// Log the progress of each running download to the log file every N seconds, at info verbosity (`-vv`)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) log_progress_secs: Option<u64>,
+
+    /// Write a JSON summary report of all downloads to this file path on exit
+    #[arg(long)]
+    pub(crate) report: Option<PathBuf>,
+
+    /// Write the URLs of all extracted videos to this file path, one per line, with titles as comments
+    #[arg(long)]
+    pub(crate) dump_urls: Option<PathBuf>,
+
+    /// Write the URLs of failed videos to this file path on exit, to be retried via --batch-file - no file is written without failures
+    #[arg(long, value_name = "PATH")]
+    pub(crate) failed_urls: Option<PathBuf>,
+
+    /// Color theme of the user interface
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) theme: Theme,
+
+    /// Render with ASCII characters only, e.g. for terminals without Unicode block or box drawing glyphs - implied by `TERM=linux` and similar
+    #[arg(long)]
+    pub(crate) ascii: bool,
+
+    /// Maximum number of videos rendered at once, scrolling along with the selection - as many as fit by default, adjustable with `+` and `-`
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) page_size: Option<u16>,
+
+    /// Apply each video's progress lines at most once per MS milliseconds, keeping the latest, to reduce lock contention on fast downloads - other lines are always applied
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) throttle_ui: Option<u64>,
+
+    /// UI refresh interval in milliseconds while downloads are active - the UI refreshes less often while idle
+    #[arg(short, long, default_value_t = 25)]
+    pub(crate) tick: u64,
+
+    /// URLs - Either target pages, containing Vimeo showcase embeds, or Vimeo showcase URLs (with --referer)
+    #[arg(required_unless_present = "batch_file")]
+    pub(crate) urls: Vec<String>,
+
+    /// File listing one URL per line, each optionally followed by a tab and the URL's own referer, overriding --referer
+    #[arg(long, value_name = "PATH")]
+    pub(crate) batch_file: Option<PathBuf>,
 
     /// Options passed to the downloader
     #[arg(last = true)]
     pub(crate) downloader_options: Vec<String>,
 }
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct ListArgs {
+    /// Print the direct media URLs resolved by the downloader, e.g. for piping into `ffmpeg`, rather than the video URLs
+    #[arg(long)]
+    pub(crate) get_url: bool,
+
+    /// Maximum number of videos resolved concurrently with --get-url - one per CPU by default
+    #[arg(long, requires = "get_url", value_parser = clap::value_parser!(u16).range(1..))]
+    pub(crate) max_concurrent_downloads: Option<u16>,
+
+    /// URLs - Either target pages, containing Vimeo showcase embeds, or Vimeo showcase URLs (with --referer)
+    #[arg(required_unless_present = "batch_file")]
+    pub(crate) urls: Vec<String>,
+
+    /// File listing one URL per line, each optionally followed by a tab and the URL's own referer, overriding --referer
+    #[arg(long, value_name = "PATH")]
+    pub(crate) batch_file: Option<PathBuf>,
+}
+
+// Signal sent to running downloads on shutdown, mapped to the session's `ShutdownSignal`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ShutdownSignalArg {
+    /// Interrupt, letting the downloader mux partially downloaded streams into a video file
+    #[default]
+    Sigint,
+    /// Terminate, aborting faster without muxing partial downloads
+    Sigterm,
+}
+
+// Reject malformed referers up front, rather than failing late on header construction.
+// The referer is kept verbatim, as the exact value may matter to referer-restricted players.
+pub(crate) fn parse_referer(referer: &str) -> Result<String, String> {
+    match Url::parse(referer) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => {
+            Ok(referer.to_owned())
+        }
+        Ok(_) => Err(format!(
+            "'{referer}' is not an HTTP(S) URL (expected e.g. 'https://example.com/page')"
+        )),
+        Err(e) => Err(format!(
+            "'{referer}' is not a valid URL: {e} (expected e.g. 'https://example.com/page')"
+        )),
+    }
+}
+
+// Parse a non-negative, possibly fractional number of seconds, e.g. `2.5`.
+fn parse_secs(secs: &str) -> Result<Duration, String> {
+    let secs: f64 = secs
+        .parse()
+        .map_err(|e| format!("'{secs}' is not a number of seconds: {e}"))?;
+
+    Duration::try_from_secs_f64(secs).map_err(|e| format!("'{secs}' seconds is out of range: {e}"))
+}
+
+// Split a `Name: Value` header, rejecting names and values which are not valid in HTTP headers.
+fn parse_header(header: &str) -> Result<(String, String), String> {
+    let Some((name, value)) = header.split_once(':') else {
+        return Err(format!(
+            "'{header}' is missing a colon between header name and value (expected e.g. 'Origin: https://example.com')"
+        ));
+    };
+    let (name, value) = (name.trim(), value.trim());
+
+    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err(format!("'{name}' is not a valid header name"));
+    }
+
+    if HeaderValue::from_str(value).is_err() {
+        return Err(format!(
+            "the value of header '{name}' contains invalid characters"
+        ));
+    }
+
+    Ok((name.to_owned(), value.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        io::{self, Write},
+        sync::{Mutex, PoisonError},
+    };
+
+    use clap::Parser;
+    use clap_complete::Shell;
+
+    use super::{
+        parse_header, try_parse_from, write_completions, Args, Command, ShutdownSignalArg,
+    };
+
+    // Held while parsing arguments, as parsing reads the environment variables
+    // changed by `environment_variables_are_overridden_by_flags`.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn parses_headers() {
+        assert_eq!(
+            parse_header("Origin: https://example.com"),
+            Ok((String::from("Origin"), String::from("https://example.com")))
+        );
+        assert_eq!(
+            parse_header("X-Token:abc"),
+            Ok((String::from("X-Token"), String::from("abc")))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        assert!(parse_header("Origin https://example.com").is_err());
+        assert!(parse_header(": value").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+    }
+
+    #[test]
+    fn shutdown_signal_defaults_to_sigint() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let shutdown_signal =
+            |args: &[&str]| match Args::try_parse_from(args).unwrap().into_parts().1 {
+                Command::Download(download) => download.shutdown_signal,
+                Command::List(_) => unreachable!(),
+            };
+
+        assert_eq!(
+            shutdown_signal(&["showcase-dl", "https://vimeo.com/123"]),
+            ShutdownSignalArg::Sigint
+        );
+        assert_eq!(
+            shutdown_signal(&[
+                "showcase-dl",
+                "--shutdown-signal",
+                "sigterm",
+                "https://vimeo.com/123"
+            ]),
+            ShutdownSignalArg::Sigterm
+        );
+    }
+
+    // Fails all writes with the given error kind.
+    struct FailingWriter(io::ErrorKind);
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(self.0.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(self.0.into())
+        }
+    }
+
+    #[test]
+    fn completions_tolerate_closed_reader() {
+        let mut script = Vec::new();
+        write_completions(Shell::Bash, &mut script).unwrap();
+        assert!(String::from_utf8(script).unwrap().contains("showcase-dl"));
+
+        write_completions(Shell::Bash, &mut FailingWriter(io::ErrorKind::BrokenPipe)).unwrap();
+        assert!(
+            write_completions(Shell::Bash, &mut FailingWriter(io::ErrorKind::StorageFull)).is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_zero_http_timeout() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+        assert!(try_parse_from([
+            "showcase-dl",
+            "--http-timeout-secs",
+            "0",
+            "https://vimeo.com/123"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_download_arguments_before_subcommand() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        assert!(
+            try_parse_from(["showcase-dl", "--no-tui", "list", "https://vimeo.com/123"]).is_err()
+        );
+        assert!(try_parse_from([
+            "showcase-dl",
+            "--limit-rate",
+            "50K",
+            "list",
+            "https://vimeo.com/123"
+        ])
+        .is_err());
+
+        // Global arguments are still accepted before the subcommand.
+        let args = try_parse_from([
+            "showcase-dl",
+            "--downloader",
+            "youtube-dl",
+            "list",
+            "https://vimeo.com/123",
+        ])
+        .unwrap();
+        assert_eq!(args.global.downloader, "youtube-dl");
+        assert!(matches!(args.into_parts().1, Command::List(_)));
+    }
+
+    #[test]
+    fn environment_variables_are_overridden_by_flags() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        env::set_var("SHOWCASE_DL_DOWNLOADER", "youtube-dl");
+        env::set_var("SHOWCASE_DL_REFERER", "https://example.com/page");
+
+        let from_env = Args::try_parse_from(["showcase-dl", "https://vimeo.com/123"]).unwrap();
+        let from_flags = Args::try_parse_from([
+            "showcase-dl",
+            "--downloader",
+            "yt-dlp-nightly",
+            "--referer",
+            "https://example.org/other",
+            "https://vimeo.com/123",
+        ])
+        .unwrap();
+
+        env::remove_var("SHOWCASE_DL_DOWNLOADER");
+        env::remove_var("SHOWCASE_DL_REFERER");
+
+        assert_eq!(from_env.global.downloader, "youtube-dl");
+        assert_eq!(
+            from_env.global.referer.as_deref(),
+            Some("https://example.com/page")
+        );
+        assert_eq!(from_flags.global.downloader, "yt-dlp-nightly");
+        assert_eq!(
+            from_flags.global.referer.as_deref(),
+            Some("https://example.org/other")
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use tracing::{info, instrument};
+
+use crate::args;
+
+// A URL to extract videos from, with the referer it needs, if any.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct BatchEntry {
+    pub(crate) url: String,
+    pub(crate) referer: Option<String>,
+}
+
+// Read a batch file, listing one URL per line, optionally followed by a tab and the URL's referer.
+#[instrument]
+pub(crate) async fn read_batch_file(path: &Path) -> Result<Vec<BatchEntry>> {
+    info!("Reading batch file '{}'...", path.display());
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .wrap_err_with(|| format!("Could not read batch file '{}'", path.display()))?;
+
+    parse_batch(&contents).wrap_err_with(|| format!("Invalid batch file '{}'", path.display()))
+}
+
+// Empty lines and `#` comments are skipped, so that the output of `--dump-urls` is a valid batch file.
+fn parse_batch(contents: &str) -> Result<Vec<BatchEntry>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let (url, maybe_referer) = match line.split_once('\t') {
+                Some((url, referer)) => (url, Some(referer.trim()).filter(|r| !r.is_empty())),
+                None => (line, None),
+            };
+
+            let referer = maybe_referer
+                .map(args::parse_referer)
+                .transpose()
+                .map_err(|e| eyre!("line {}: {e}", i + 1))?;
+
+            Ok(BatchEntry {
+                url: url.trim().to_owned(),
+                referer,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_batch, BatchEntry};
+
+    #[test]
+    fn parses_urls_with_and_without_referer() {
+        let batch = parse_batch(
+            "# Showcase\nhttps://vimeo.com/showcase/123\thttps://example.com/page\n\nhttps://vimeo.com/456\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            batch,
+            [
+                BatchEntry {
+                    url: String::from("https://vimeo.com/showcase/123"),
+                    referer: Some(String::from("https://example.com/page")),
+                },
+                BatchEntry {
+                    url: String::from("https://vimeo.com/456"),
+                    referer: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_referers_with_line_number() {
+        let e =
+            parse_batch("https://vimeo.com/456\nhttps://vimeo.com/789\tnot a url\n").unwrap_err();
+
+        assert!(e.to_string().starts_with("line 2: "));
+    }
+}
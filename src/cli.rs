@@ -0,0 +1,195 @@
+use std::{
+    io::{self, IsTerminal},
+    path::Path,
+    time::Duration,
+};
+
+use color_eyre::{eyre::Result, Report};
+use tracing::info;
+
+use crate::{
+    args::{self, Command, DownloadArgs, GlobalArgs, ListArgs, ShutdownSignalArg},
+    batch::{self, BatchEntry},
+    dump, error, headless, notify, progress_log, report,
+    session::{Config, Session, ShutdownSignal},
+    trace,
+    ui::Ui,
+    webhook,
+};
+
+/// Run the terminal user interface, configured by command line arguments.
+///
+/// # Errors
+///
+/// Fails if the terminal can not be captured, or if extracting videos fails.
+pub async fn run() -> Result<()> {
+    error::color_eyre_install()?;
+
+    let args = args::parse();
+
+    if let Some(shell) = args.generate_completions {
+        args::print_completions(shell)?;
+        return Ok(());
+    }
+
+    let (global, command) = args.into_parts();
+
+    let _telemetry_guard = trace::init(&global)?;
+
+    match command {
+        Command::Download(download_args) => download(global, download_args).await,
+        Command::List(list_args) => list(global, list_args).await,
+    }
+}
+
+// Session configuration shared by all subcommands.
+fn config(global: GlobalArgs) -> Config {
+    Config {
+        downloader: global.downloader,
+        referer: global.referer,
+        referer_from_url: global.referer_from_url,
+        referer_from_showcase: global.referer_from_showcase,
+        video_password: global.video_password,
+        headers: global.headers,
+        http_timeout: Duration::from_secs(global.http_timeout_secs),
+        // Saturate on 32 bit targets, where no more videos could be held anyway.
+        video_limit: global
+            .limit
+            .map(|limit| usize::try_from(limit).unwrap_or(usize::MAX)),
+        url_contains: global.url_contains,
+        ..Config::default()
+    }
+}
+
+// URLs given on the command line, using the global referer, followed by those of the batch file, if any.
+async fn batch_entries(urls: Vec<String>, batch_file: Option<&Path>) -> Result<Vec<BatchEntry>> {
+    let mut entries: Vec<BatchEntry> = urls
+        .into_iter()
+        .map(|url| BatchEntry { url, referer: None })
+        .collect();
+
+    if let Some(batch_file) = batch_file {
+        entries.extend(batch::read_batch_file(batch_file).await?);
+    }
+
+    Ok(entries)
+}
+
+async fn download(global: GlobalArgs, args: DownloadArgs) -> Result<()> {
+    let entries = batch_entries(args.urls, args.batch_file.as_deref()).await?;
+
+    let session = Session::new(Config {
+        downloader_options: args.downloader_options,
+        limit_rate: args.limit_rate,
+        max_filesize: args.max_filesize,
+        output_template: args.output_template,
+        windows_filenames: args.windows_filenames,
+        embed_metadata: args.embed_metadata,
+        embed_thumbnail: args.embed_thumbnail,
+        subtitles: args.subtitles,
+        auto_subtitles: args.auto_subtitles,
+        simulate: args.simulate,
+        trim_filenames: args.trim_filenames,
+        concurrent_fragments: args.concurrent_fragments,
+        continue_partial_downloads: !args.no_continue,
+        max_concurrent_downloads: args.max_concurrent_downloads.map(usize::from),
+        download_attempts: args.download_attempts,
+        min_sleep_interval: args.min_sleep_interval,
+        max_sleep_interval: args.max_sleep_interval,
+        retry_sleep: args.retry_sleep,
+        shutdown_signal: match args.shutdown_signal {
+            ShutdownSignalArg::Sigint => ShutdownSignal::Sigint,
+            ShutdownSignalArg::Sigterm => ShutdownSignal::Sigterm,
+        },
+        line_throttle: args.throttle_ui.map(Duration::from_millis),
+        ..config(global)
+    })?;
+    let state = session.state();
+
+    let do_work = async {
+        tokio::try_join!(
+            session.download_batch(
+                entries
+                    .iter()
+                    .map(|entry| (entry.url.as_str(), entry.referer.as_deref())),
+            ),
+            // Dump the URLs as soon as all videos are extracted, rather than once all downloads have ended.
+            async {
+                if let Some(ref dump_urls_path) = args.dump_urls {
+                    state.extracted().await;
+                    dump::write_urls(dump_urls_path, state).await?;
+                }
+                Ok::<(), Report>(())
+            }
+        )?;
+
+        if args.notify {
+            notify::notify_done(state).await;
+        }
+
+        if let Some(webhook_url) = args.webhook_url {
+            webhook::post_summary(webhook_url, state).await;
+        }
+
+        Ok::<(), Report>(())
+    };
+
+    let maybe_progress_log = args
+        .log_progress_secs
+        .map(|secs| tokio::spawn(progress_log::run(state.clone(), Duration::from_secs(secs))));
+
+    // Capturing the terminal fails without a TTY, e.g. under a CI runner.
+    let work_result = if args.no_tui || !io::stdout().is_terminal() {
+        if !args.no_tui {
+            info!("Stdout is not a terminal, running without terminal user interface.");
+        }
+        headless::run(state.clone(), do_work).await
+    } else {
+        Ui::new(args.theme, args.ascii, args.page_size.map(usize::from))
+            .event_loop(state.clone(), args.tick, args.exit_on_done, do_work)
+            .await
+    };
+
+    if let Some(progress_log) = maybe_progress_log {
+        progress_log.abort();
+    }
+
+    // Failed runs are the ones to retry and audit,
+    // so the failed URLs and the report are written before propagating the error.
+    if let Some(failed_urls_path) = args.failed_urls {
+        dump::write_failed_urls(&failed_urls_path, state).await?;
+    }
+
+    if let Some(report_path) = args.report {
+        report::write_report(&report_path, state).await?;
+    }
+
+    work_result
+}
+
+// Print the extracted videos, without capturing the terminal.
+async fn list(global: GlobalArgs, args: ListArgs) -> Result<()> {
+    let entries = batch_entries(args.urls, args.batch_file.as_deref()).await?;
+
+    let session = Session::new(Config {
+        extract_only: true,
+        max_concurrent_downloads: args.max_concurrent_downloads.map(usize::from),
+        ..config(global)
+    })?;
+
+    session
+        .download_batch(
+            entries
+                .iter()
+                .map(|entry| (entry.url.as_str(), entry.referer.as_deref())),
+        )
+        .await?;
+
+    if args.get_url {
+        print!("{}", dump::format_media_urls(session.state()).await?);
+    } else {
+        print!("{}", dump::format_urls(session.state()).await?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::{fmt::Write, num::NonZeroUsize, path::Path, thread};
+
+use color_eyre::eyre::{Result, WrapErr};
+use futures::{stream, StreamExt};
+use tracing::{info, instrument, warn};
+
+use crate::state::{video::Stage, State};
+
+// Write the URLs of all extracted videos to the given file path, formatted like `format_urls`.
+#[instrument(skip(state))]
+pub(crate) async fn write_urls(path: &Path, state: &State) -> Result<()> {
+    let urls = format_urls(state).await?;
+
+    info!("Writing video URLs to '{}'...", path.display());
+    tokio::fs::write(path, urls)
+        .await
+        .wrap_err_with(|| format!("Could not write video URLs to '{}'", path.display()))?;
+
+    Ok(())
+}
+
+// Format the URLs of all extracted videos, one per line.
+// Titles are written as `#` comments preceding their video's URL.
+pub(crate) async fn format_urls(state: &State) -> Result<String> {
+    let mut urls = String::new();
+    for video in &*state.videos().await {
+        let video = video.read().await;
+
+        if let Some(title) = video.title() {
+            // Keep multi-line titles within their comment.
+            writeln!(urls, "# {}", title.lines().collect::<Vec<_>>().join(" "))?;
+        }
+        writeln!(urls, "{}", video.url())?;
+    }
+
+    Ok(urls)
+}
+
+// Write the URLs of all failed videos to the given file path, formatted like `format_failed_urls`.
+// Without failures, no file is written.
+#[instrument(skip(state))]
+pub(crate) async fn write_failed_urls(path: &Path, state: &State) -> Result<()> {
+    let Some(urls) = format_failed_urls(state).await? else {
+        info!("No failed videos, not writing failed video URLs.");
+        return Ok(());
+    };
+
+    info!("Writing failed video URLs to '{}'...", path.display());
+    tokio::fs::write(path, urls)
+        .await
+        .wrap_err_with(|| format!("Could not write failed video URLs to '{}'", path.display()))?;
+
+    Ok(())
+}
+
+// Format the URLs of all failed videos as a batch file, to be retried via `--batch-file`.
+// Geo-blocked videos count as failed, as they may be retried from another region, e.g. via a VPN.
+// Each URL is followed by a tab and the video's referer, if any, and preceded by its title as `#` comment.
+// Returns `None` if no video failed.
+async fn format_failed_urls(state: &State) -> Result<Option<String>> {
+    let mut urls = String::new();
+    for video in &*state.videos().await {
+        let video_read = video.read().await;
+        if !matches!(video_read.stage(), Stage::Failed | Stage::GeoBlocked { .. }) {
+            continue;
+        }
+
+        if let Some(title) = video_read.title() {
+            writeln!(urls, "# {}", title.lines().collect::<Vec<_>>().join(" "))?;
+        }
+        match video.referer() {
+            Some(referer) => writeln!(urls, "{}\t{referer}", video_read.url())?,
+            None => writeln!(urls, "{}", video_read.url())?,
+        }
+    }
+
+    Ok(Some(urls).filter(|urls| !urls.is_empty()))
+}
+
+// Format the direct media URLs of all extracted videos, resolved by the downloader, one per line.
+// Titles are written as `#` comments preceding their video's media URLs, like in `format_urls`.
+// Videos which fail to resolve are listed as comments, so that a single failure does not fail the listing.
+// The downloader resolves at most `--max-concurrent-downloads` videos at once, or one per CPU by default.
+#[instrument(skip(state))]
+pub(crate) async fn format_media_urls(state: &State) -> Result<String> {
+    let videos = state.videos().await.clone();
+    let max_concurrent_resolves = state
+        .max_concurrent_downloads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+    let resolved: Vec<_> = stream::iter(videos.iter().map(|video| video.resolve_media_urls(state)))
+        .buffered(max_concurrent_resolves)
+        .collect()
+        .await;
+
+    let mut urls = String::new();
+    for (video, maybe_media_urls) in videos.iter().zip(resolved) {
+        let video = video.read().await;
+
+        if let Some(title) = video.title() {
+            writeln!(urls, "# {}", title.lines().collect::<Vec<_>>().join(" "))?;
+        }
+        match maybe_media_urls {
+            Ok(media_urls) => {
+                for media_url in media_urls {
+                    writeln!(urls, "{media_url}")?;
+                }
+            }
+            Err(e) => {
+                warn!("{e:#}");
+                writeln!(urls, "# Could not resolve {}: {e}", video.url())?;
+            }
+        }
+    }
+
+    Ok(urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::{format_failed_urls, format_urls};
+    use crate::{
+        session::{Config, ShutdownSignal},
+        state::{
+            video::{downloader::ScriptedDownloader, Stage, Video},
+            State,
+        },
+    };
+
+    #[tokio::test]
+    async fn urls_are_extracted_while_downloads_are_still_running() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("<html><head><title>My Video</title></head></html>"),
+            )
+            .mount(&server)
+            .await;
+
+        // The download runs until interrupted.
+        let state = Arc::new(State::new(Config::default()).with_downloader_command(
+            ScriptedDownloader {
+                lines: vec!["[download] Destination: My Video.mp4"],
+                exit_code: None,
+            },
+        ));
+        let extraction = state.begin_extraction();
+        let player_url = server.uri();
+        let processing = tokio::spawn({
+            let state = state.clone();
+            async move {
+                crate::process::simple_player::process_simple_player(
+                    &player_url,
+                    None,
+                    state,
+                    extraction,
+                )
+                .await
+            }
+        });
+
+        tokio::time::timeout(Duration::from_secs(10), state.extracted())
+            .await
+            .expect("extraction should end before the download does");
+
+        assert!(format_urls(&state).await.unwrap().contains(&server.uri()));
+        let video = state.video(1).await.unwrap();
+        assert!(!matches!(
+            video.read().await.stage(),
+            Stage::Finished | Stage::Failed
+        ));
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while video.read().await.stage().process_id().is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the download should start");
+        video
+            .initiate_shutdown(ShutdownSignal::Sigint)
+            .await
+            .unwrap();
+        processing.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_urls_form_a_batch_file() {
+        let state = State::new(Config::default());
+        assert_eq!(format_failed_urls(&state).await.unwrap(), None);
+
+        let finished = Arc::new(Video::new("https://vimeo.com/1", None::<String>));
+        let failed = Arc::new(Video::new_with_title(
+            "https://vimeo.com/2",
+            Some("https://example.com/page"),
+            Some(String::from("Second")),
+        ));
+        let failed_without_referer = Arc::new(Video::new("https://vimeo.com/3", None::<String>));
+        let geo_blocked = Arc::new(Video::new("https://vimeo.com/4", None::<String>));
+        for video in [&finished, &failed, &failed_without_referer, &geo_blocked] {
+            state.push_video(video.clone()).await;
+        }
+        finished.set_stage_finished().await;
+        failed.set_stage_failed().await;
+        failed_without_referer.set_stage_failed().await;
+        geo_blocked
+            .set_stage_geo_blocked(String::from("not available in your country"))
+            .await;
+
+        assert_eq!(
+            format_failed_urls(&state).await.unwrap().as_deref(),
+            Some("# Second\nhttps://vimeo.com/2\thttps://example.com/page\nhttps://vimeo.com/3\nhttps://vimeo.com/4\n")
+        );
+    }
+}
@@ -21,9 +21,10 @@ pub(crate) fn color_eyre_install() -> Result<()> {
 
     // Replace `panic_hook.install()`.
     let panic_hook = panic_hook.into_panic_hook();
+    // Panicking within the panic hook would abort with an unreadable screen,
+    // e.g. if the panic was caused by a broken terminal. Restoring the terminal is therefore best-effort.
     std::panic::set_hook(Box::new(move |panic_info| {
-        let terminal = Ui::make_terminal().expect("make terminal for panic handler");
-        Ui::release_terminal(terminal).expect("release terminal for panic handler");
+        Ui::restore_terminal();
 
         panic_hook(panic_info);
     }));
@@ -1,2 +1,3 @@
 pub(crate) mod embeds;
+pub(crate) mod iframe;
 pub(crate) mod player;
@@ -1,33 +1,33 @@
 use std::sync::Arc;
 
-use color_eyre::eyre::{bail, Result};
+use color_eyre::eyre::Result;
 use futures::{stream, TryStreamExt};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use reqwest::Url;
 use tracing::{debug, info, instrument, trace};
 
-use crate::{state::State, util};
+use crate::{
+    state::{Extraction, State},
+    util,
+};
 
-static REGEX_VIDEO_IFRAME: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(
-        r#"<iframe[^>]* (?:data-)?src="(?P<embed_url>https://player\.vimeo\.com/video/[^"]+)""#,
-    )
-    .unwrap()
-});
+// Player embed URLs may be protocol-relative, relative or proxied, e.g. `//player.vimeo.com/video/123`.
+static REGEX_PLAYER_EMBED_URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"player\.vimeo\.com/video/.").unwrap());
 
 #[instrument(skip(state))]
-pub(crate) async fn extract_and_download_embeds(url: Url, state: Arc<State>) -> Result<()> {
-    let referer = Some(format!(
-        "{}://{}/",
-        url.scheme(),
-        url.host_str().unwrap_or_default()
-    ));
+pub(crate) async fn extract_and_download_embeds(
+    url: Url,
+    state: Arc<State>,
+    extraction: Extraction,
+) -> Result<()> {
+    let referer = Some(util::origin_referer(&url));
 
     info!("Fetch source page...");
     state.set_stage_fetching_source(url.as_str()).await;
 
-    let response_text = util::fetch_with_retry(url, None, None)
+    let response_text = util::fetch_with_retry(url.clone(), None, None)
         .await?
         .text()
         .await?;
@@ -40,9 +40,16 @@ pub(crate) async fn extract_and_download_embeds(url: Url, state: Arc<State>) ->
         crate::process::showcase::process_showcases(
             &response_text,
             referer.as_deref(),
-            state.clone()
+            state.clone(),
+            extraction.clone()
         ),
-        process_simple_embeds(&response_text, referer.as_deref(), state.clone())
+        process_simple_embeds(
+            &response_text,
+            &url,
+            referer.as_deref(),
+            state.clone(),
+            extraction
+        )
     )?;
 
     Ok(())
@@ -51,32 +58,185 @@ pub(crate) async fn extract_and_download_embeds(url: Url, state: Arc<State>) ->
 #[instrument(skip(page_body, state))]
 async fn process_simple_embeds(
     page_body: &str,
+    base_url: &Url,
     referer: Option<&str>,
     state: Arc<State>,
+    extraction: Extraction,
 ) -> Result<()> {
-    stream::iter(REGEX_VIDEO_IFRAME.captures_iter(page_body).map(Ok))
-        .try_for_each_concurrent(None, |captures| {
-            let state = state.clone();
-            async move {
-                debug!("{captures:#?}");
-
-                match captures.name("embed_url") {
-                    Some(embed_url_match) => {
-                        let embed_url =
-                            htmlize::unescape_attribute(embed_url_match.as_str()).into_owned();
-
-                        crate::process::simple_player::process_simple_player(
-                            &embed_url, referer, state,
-                        )
-                        .await?;
-
-                        Ok(())
-                    }
-                    None => bail!("Capture group did not match named 'embed_url'"),
-                }
-            }
-        })
-        .await?;
+    let embed_urls =
+        super::iframe::iframe_sources(page_body, |source| REGEX_PLAYER_EMBED_URL.is_match(source));
+
+    // Filter before applying the video limit, so that unwanted embeds do not count towards it.
+    let mut wanted_embed_urls = Vec::with_capacity(embed_urls.len());
+    for embed_url in embed_urls {
+        let embed_url = normalize_embed_url(&embed_url, base_url)?;
+
+        if state.is_embed_wanted(embed_url.as_str()) {
+            wanted_embed_urls.push(embed_url);
+        } else {
+            debug!("Skipping unwanted embed '{embed_url}'.");
+        }
+    }
+
+    let remaining_video_slots = state.remaining_video_slots().await;
+    stream::iter(
+        extraction
+            .branch(wanted_embed_urls.into_iter().take(remaining_video_slots))
+            .into_iter()
+            .map(Ok),
+    )
+    .try_for_each_concurrent(None, |(embed_url, extraction)| {
+        let state = state.clone();
+        async move {
+            // Each embed carries its own referer, as pages may aggregate embeds from multiple origins.
+            let embed_referer = embed_referer(&embed_url, referer);
+
+            crate::process::simple_player::process_simple_player(
+                embed_url.as_str(),
+                embed_referer.as_deref(),
+                state,
+                extraction,
+            )
+            .await
+        }
+    })
+    .await?;
 
     Ok(())
 }
+
+// Resolve protocol-relative and relative embed URLs against the source page URL.
+// Protocol-relative URLs are resolved to `https:`, regardless of the source page's scheme.
+fn normalize_embed_url(embed_url: &str, base_url: &Url) -> Result<Url> {
+    let embed_url = if embed_url.starts_with("//") {
+        Url::parse(&format!("https:{embed_url}"))?
+    } else {
+        base_url.join(embed_url)?
+    };
+    debug!("Normalized embed URL: {embed_url}");
+
+    Ok(embed_url)
+}
+
+// Player URLs passed directly lack an embedding page to be referred to by.
+// Some players refuse to play without any referer, and accept the player URL's own origin instead.
+// An explicitly given referer always takes precedence.
+pub(crate) fn player_referer(
+    player_url: &Url,
+    referer: Option<&str>,
+    referer_from_url: bool,
+) -> Option<String> {
+    match referer {
+        Some(referer) => Some(referer.to_owned()),
+        None if referer_from_url => Some(util::origin_referer(player_url)),
+        None => None,
+    }
+}
+
+// Embeds served via a proxy on another origin are referred to by that origin.
+// Embeds served straight from the player's host are referred to by the source page's origin.
+pub(crate) fn embed_referer(embed_url: &Url, page_referer: Option<&str>) -> Option<String> {
+    if embed_url
+        .host_str()
+        .is_some_and(|host| super::player::is_domain_or_subdomain(host, "vimeo.com"))
+    {
+        page_referer.map(ToOwned::to_owned)
+    } else {
+        Some(util::origin_referer(embed_url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use super::{embed_referer, normalize_embed_url, player_referer, REGEX_PLAYER_EMBED_URL};
+    use crate::{extract::iframe::iframe_sources, state::video::Video};
+
+    // Extract and normalize the first player embed URL, then wrap it in a `Video`.
+    fn extract_video(page_body: &str) -> Video {
+        let base_url = Url::parse("https://example.com/page/").unwrap();
+        let embed_urls =
+            iframe_sources(page_body, |source| REGEX_PLAYER_EMBED_URL.is_match(source));
+        let embed_url = normalize_embed_url(&embed_urls[0], &base_url).unwrap();
+
+        Video::new(embed_url.as_str(), None::<String>)
+    }
+
+    #[test]
+    fn unlisted_video_hash_path_segment_is_preserved() {
+        let video = extract_video(
+            r#"<iframe width="640" src="https://player.vimeo.com/video/12345/abcdef0123?badge=0&amp;autopause=0"></iframe>"#,
+        );
+
+        assert_eq!(
+            video.url(),
+            "https://player.vimeo.com/video/12345/abcdef0123?badge=0&autopause=0"
+        );
+    }
+
+    #[test]
+    fn unlisted_video_hash_query_parameter_is_preserved() {
+        let video = extract_video(
+            r#"<iframe class="embed" data-src="//player.vimeo.com/video/12345?h=abcdef0123&amp;app_id=58479"></iframe>"#,
+        );
+
+        assert_eq!(
+            video.url(),
+            "https://player.vimeo.com/video/12345?h=abcdef0123&app_id=58479"
+        );
+    }
+
+    #[test]
+    fn player_referer_is_derived_from_player_url_only_on_request() {
+        let player_url = Url::parse("https://player.vimeo.com/video/12345?h=abcdef0123").unwrap();
+
+        assert_eq!(player_referer(&player_url, None, false), None);
+        assert_eq!(
+            player_referer(&player_url, None, true).as_deref(),
+            Some("https://player.vimeo.com/")
+        );
+        assert_eq!(
+            player_referer(&player_url, Some("https://example.com/page"), true).as_deref(),
+            Some("https://example.com/page")
+        );
+    }
+
+    #[test]
+    fn player_embeds_are_referred_to_by_the_source_page() {
+        let video = extract_video(
+            r#"<iframe src="https://player.vimeo.com/video/12345?h=abcdef0123"></iframe>"#,
+        );
+        let embed_url = Url::parse(video.url()).unwrap();
+
+        assert_eq!(
+            embed_referer(&embed_url, Some("https://example.com/")).as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(embed_referer(&embed_url, None), None);
+    }
+
+    #[test]
+    fn proxied_embeds_are_referred_to_by_their_own_origin() {
+        let video = extract_video(
+            r#"<iframe src="https://cdn.example.org/embed/player.vimeo.com/video/12345"></iframe>"#,
+        );
+        let embed_url = Url::parse(video.url()).unwrap();
+
+        assert_eq!(
+            embed_referer(&embed_url, Some("https://example.com/")).as_deref(),
+            Some("https://cdn.example.org/")
+        );
+    }
+
+    #[test]
+    fn relative_proxied_embeds_are_referred_to_by_the_source_page_origin() {
+        let video = extract_video(r#"<iframe src="/vimeo/player.vimeo.com/video/12345"></iframe>"#);
+        let embed_url = Url::parse(video.url()).unwrap();
+
+        assert_eq!(
+            embed_referer(&embed_url, None).as_deref(),
+            Some("https://example.com/")
+        );
+    }
+}
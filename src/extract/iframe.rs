@@ -0,0 +1,99 @@
+use once_cell::sync::Lazy;
+use scraper::{Html, Selector};
+
+static SELECTOR_IFRAME: Lazy<Selector> = Lazy::new(|| Selector::parse("iframe").unwrap());
+
+// Lazy-loaded iframes carry the actual embed URL in `data-src`, while `src` may hold a placeholder.
+const SOURCE_ATTRIBUTES: [&str; 2] = ["data-src", "src"];
+
+// Extract the source URLs of all iframes in the page, regardless of attribute order and quoting.
+// Of each iframe's source attributes, the first one accepted by `is_wanted` is used.
+// Character references in attribute values, such as `&amp;`, are decoded by the HTML parser.
+pub(crate) fn iframe_sources(page_body: &str, is_wanted: impl Fn(&str) -> bool) -> Vec<String> {
+    // Parsing is lenient, as is any browser's: Malformed HTML yields a best-effort document.
+    Html::parse_document(page_body)
+        .select(&SELECTOR_IFRAME)
+        .filter_map(|iframe| {
+            SOURCE_ATTRIBUTES
+                .iter()
+                .filter_map(|&name| iframe.value().attr(name))
+                .find(|&source| is_wanted(source))
+                .map(ToOwned::to_owned)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::iframe_sources;
+
+    fn sources(page_body: &str) -> Vec<String> {
+        iframe_sources(page_body, |source| source.contains("vimeo"))
+    }
+
+    #[test]
+    fn attribute_order_is_irrelevant() {
+        assert_eq!(
+            sources(
+                r#"<iframe src="https://player.vimeo.com/video/1" class="embed" width="640"></iframe>"#
+            ),
+            ["https://player.vimeo.com/video/1"]
+        );
+        assert_eq!(
+            sources(
+                r#"<iframe class="embed" width="640" src="https://player.vimeo.com/video/1"></iframe>"#
+            ),
+            ["https://player.vimeo.com/video/1"]
+        );
+    }
+
+    #[test]
+    fn single_quoted_attributes_are_read() {
+        assert_eq!(
+            sources("<iframe class='embed' src='https://player.vimeo.com/video/1?h=ab&amp;app_id=2'></iframe>"),
+            ["https://player.vimeo.com/video/1?h=ab&app_id=2"]
+        );
+    }
+
+    #[test]
+    fn lazy_loaded_source_is_preferred() {
+        assert_eq!(
+            sources(
+                r#"<iframe src="https://player.vimeo.com/placeholder" data-src="https://player.vimeo.com/video/1"></iframe>"#
+            ),
+            ["https://player.vimeo.com/video/1"]
+        );
+    }
+
+    #[test]
+    fn unwanted_sources_are_skipped() {
+        assert!(sources(
+            r#"<iframe src="https://example.com/ad"></iframe><div src="https://vimeo.com"></div>"#
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn every_quoting_style_is_read() {
+        let expected = ["https://player.vimeo.com/video/1?h=ab&app_id=2"];
+
+        assert_eq!(
+            sources(
+                r#"<iframe src="https://player.vimeo.com/video/1?h=ab&amp;app_id=2"></iframe>"#
+            ),
+            expected
+        );
+        assert_eq!(
+            sources("<iframe src='https://player.vimeo.com/video/1?h=ab&amp;app_id=2'></iframe>"),
+            expected
+        );
+        assert_eq!(
+            sources("<iframe src=https://player.vimeo.com/video/1?h=ab&amp;app_id=2 width=640></iframe>"),
+            expected
+        );
+        assert_eq!(
+            sources("<iframe width=640 src=https://player.vimeo.com/video/1?h=ab&amp;app_id=2></iframe>"),
+            expected
+        );
+    }
+}
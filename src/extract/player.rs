@@ -4,14 +4,103 @@ use color_eyre::eyre::Result;
 use reqwest::Url;
 use tracing::{info, instrument};
 
-use crate::state::State;
+use crate::state::{Extraction, State};
+
+// Player hosts, including their subdomains, such as `player.vimeo.com` or `www.youtube.com`.
+const PLAYER_DOMAINS: [&str; 3] = ["vimeo.com", "youtube.com", "youtu.be"];
 
 pub(crate) fn is_player_url(url: &Url) -> bool {
-    let host_str = url.host_str().unwrap_or_default();
+    url.host_str().is_some_and(|host| {
+        PLAYER_DOMAINS
+            .iter()
+            .any(|domain| is_domain_or_subdomain(host, domain))
+    })
+}
+
+// Compare whole labels, so that look-alike hosts such as `evilvimeo.com` do not match `vimeo.com`.
+pub(crate) fn is_domain_or_subdomain(host: &str, domain: &str) -> bool {
+    // Fully qualified hosts may carry a trailing dot.
+    let host = host.strip_suffix('.').unwrap_or(host);
+
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|subdomains| subdomains.ends_with('.'))
+}
+
+// Albums, `/album/<id>`, are processed like showcases.
+fn is_album_url(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let mut segments = url
+        .path()
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .split('/');
+
+    host == "vimeo.com"
+        && segments.next() == Some("album")
+        && segments
+            .next()
+            .is_some_and(|id| !id.is_empty() && id.bytes().all(|byte| byte.is_ascii_digit()))
+        && segments.next().is_none()
+}
 
-    host_str.ends_with("vimeo.com")
-        || host_str.ends_with("youtube.com")
-        || host_str.ends_with("youtu.be")
+// Playlists and other album pages contain many entries, which are enumerated by the downloader.
+pub(crate) fn is_playlist_url(url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+
+    if is_domain_or_subdomain(host, "youtube.com") || is_domain_or_subdomain(host, "youtu.be") {
+        url.query_pairs().any(|(key, _)| key == "list")
+    } else if is_domain_or_subdomain(host, "vimeo.com") {
+        url.path().contains("/album/")
+    } else {
+        false
+    }
+}
+
+// Single videos, which the downloader handles directly.
+// Matching host and path separately, so that query parameter order does not matter.
+fn is_simple_player_url(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    // Desktop and mobile sites serve the same videos.
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(host);
+    let path = url.path();
+
+    match host {
+        "player.vimeo.com" => path.starts_with("/video/"),
+        "vimeo.com" => is_canonical_vimeo_video_path(path),
+        "youtube.com" => {
+            (path == "/watch" && url.query_pairs().any(|(key, _)| key == "v"))
+                || path.starts_with("/live/")
+                || path.starts_with("/shorts/")
+        }
+        "youtu.be" => path.len() > 1,
+        _ => false,
+    }
+}
+
+// Canonical video pages, `/<id>`, or unlisted ones, `/<id>/<hash>`.
+// Other paths, such as `/showcase/<id>` or `/event/<id>`, start with a non-numeric segment.
+fn is_canonical_vimeo_video_path(path: &str) -> bool {
+    let mut segments = path
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .split('/');
+
+    let is_id = segments
+        .next()
+        .is_some_and(|id| !id.is_empty() && id.bytes().all(|byte| byte.is_ascii_digit()));
+    let is_hash_or_none = segments
+        .next()
+        .is_none_or(|hash| !hash.is_empty() && hash.bytes().all(|byte| byte.is_ascii_hexdigit()));
+
+    is_id && is_hash_or_none && segments.next().is_none()
 }
 
 #[instrument(skip(state))]
@@ -19,33 +108,161 @@ pub(crate) async fn download_from_player(
     url: Url,
     referer: Option<&str>,
     state: Arc<State>,
+    extraction: Extraction,
 ) -> Result<()> {
     info!("Download from player...");
     state.set_stage_processing().await;
 
     let url_str = url.as_str();
 
+    if is_album_url(&url) {
+        return crate::process::album::process_album(url_str, referer, state.clone(), extraction)
+            .await;
+    }
+
+    if is_playlist_url(&url) {
+        return crate::process::playlist::process_playlist(
+            url_str,
+            referer,
+            state.clone(),
+            extraction,
+        )
+        .await;
+    }
+
     if url_str.starts_with("https://vimeo.com/showcase/") {
-        return crate::process::showcase::process_showcase(url_str, referer, state.clone()).await;
+        return crate::process::showcase::process_showcase(
+            url_str,
+            referer,
+            state.clone(),
+            extraction,
+        )
+        .await;
     }
 
     if url_str.starts_with("https://vimeo.com/event/") {
-        return crate::process::event::process_event(url_str, state.clone()).await;
+        return crate::process::event::process_event(url_str, state.clone(), extraction).await;
         // No referer necessary.
     }
 
-    if url_str.starts_with("https://player.vimeo.com/video/")
-        || url_str.starts_with("https://www.youtube.com/watch?v=")
-        || url_str.starts_with("https://www.youtube.com/live/")
-        || url_str.starts_with("https://youtu.be/")
-    {
+    if is_simple_player_url(&url) {
         return crate::process::simple_player::process_simple_player(
             url_str,
             referer,
             state.clone(),
+            extraction,
         )
         .await;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use super::{is_album_url, is_player_url, is_playlist_url, is_simple_player_url};
+
+    fn is_player(url: &str) -> bool {
+        is_player_url(&Url::parse(url).unwrap())
+    }
+
+    fn is_simple_player(url: &str) -> bool {
+        is_simple_player_url(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn player_hosts_and_subdomains_match() {
+        assert!(is_player("https://vimeo.com/showcase/123"));
+        assert!(is_player("https://player.vimeo.com/video/123"));
+        assert!(is_player("https://youtube.com/watch?v=abc"));
+        assert!(is_player("https://www.youtube.com/watch?v=abc"));
+        assert!(is_player("https://m.youtube.com/watch?v=abc"));
+        assert!(is_player("https://youtu.be/abc"));
+        assert!(is_player("https://player.vimeo.com./video/123"));
+    }
+
+    #[test]
+    fn look_alike_hosts_do_not_match() {
+        assert!(!is_player("https://evilvimeo.com/video/123"));
+        assert!(!is_player("https://notyoutube.com/watch?v=abc"));
+        assert!(!is_player("https://fakeyoutu.be/abc"));
+        assert!(!is_player("https://vimeo.com.example.com/video/123"));
+        assert!(!is_player("https://example.com/vimeo.com"));
+    }
+
+    #[test]
+    fn album_urls_match() {
+        let is_album = |url: &str| is_album_url(&Url::parse(url).unwrap());
+
+        assert!(is_album("https://vimeo.com/album/123"));
+        assert!(is_album("https://www.vimeo.com/album/123/"));
+        assert!(!is_album("https://vimeo.com/album/123/video/456"));
+        assert!(!is_album("https://vimeo.com/album/"));
+        assert!(!is_album("https://vimeo.com/showcase/123"));
+    }
+
+    #[test]
+    fn playlist_urls_match() {
+        let is_playlist = |url: &str| is_playlist_url(&Url::parse(url).unwrap());
+
+        assert!(is_playlist("https://www.youtube.com/playlist?list=PL123"));
+        assert!(is_playlist(
+            "https://www.youtube.com/watch?v=abc&list=PL123"
+        ));
+        assert!(is_playlist("https://youtu.be/abc?list=PL123"));
+        assert!(is_playlist("https://vimeo.com/user123/album/456"));
+        assert!(!is_playlist("https://www.youtube.com/watch?v=abc"));
+        assert!(!is_playlist("https://vimeo.com/123456789?list=1"));
+        assert!(!is_playlist("https://example.com/album/456"));
+        assert!(!is_playlist("https://example.com/watch?list=PL123"));
+    }
+
+    #[test]
+    fn shorts_url_is_simple_player() {
+        assert!(is_simple_player("https://www.youtube.com/shorts/abc123"));
+        assert!(is_simple_player("https://youtube.com/shorts/abc123"));
+    }
+
+    #[test]
+    fn mobile_watch_url_is_simple_player() {
+        assert!(is_simple_player("https://m.youtube.com/watch?v=abc123"));
+        assert!(is_simple_player(
+            "https://m.youtube.com/watch?feature=share&v=abc123"
+        ));
+    }
+
+    #[test]
+    fn non_video_urls_are_not_simple_players() {
+        assert!(!is_simple_player(
+            "https://www.youtube.com/watch?feature=share"
+        ));
+        assert!(!is_simple_player("https://www.youtube.com/@channel"));
+        assert!(!is_simple_player("https://youtu.be/"));
+    }
+
+    #[test]
+    fn canonical_vimeo_urls_are_simple_players() {
+        assert!(is_simple_player("https://vimeo.com/123456789"));
+        assert!(is_simple_player("https://www.vimeo.com/123456789/"));
+        assert!(is_simple_player("https://vimeo.com/123456789?share=copy"));
+    }
+
+    #[test]
+    fn unlisted_vimeo_urls_are_simple_players() {
+        assert!(is_simple_player("https://vimeo.com/123456789/abcdef0123"));
+    }
+
+    #[test]
+    fn other_vimeo_pages_are_not_simple_players() {
+        assert!(!is_simple_player("https://vimeo.com/showcase/123"));
+        assert!(!is_simple_player("https://vimeo.com/event/123"));
+        assert!(!is_simple_player("https://vimeo.com/user123"));
+        assert!(!is_simple_player("https://vimeo.com/"));
+        assert!(!is_simple_player("https://vimeo.com/123456789/not-a-hash"));
+        assert!(!is_simple_player(
+            "https://vimeo.com/123456789/abcdef/extra"
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+use std::{future::Future, sync::Arc};
+
+use color_eyre::eyre::Result;
+use tokio::sync::{broadcast::error::RecvError, oneshot};
+use tracing::{error, info, instrument, warn, Instrument};
+
+use crate::{session::Event, state::State};
+
+// Drive the application process futures without capturing the terminal,
+// printing a line per video stage change instead, e.g. when running under a CI runner.
+// Ctrl+C shuts down running downloads gracefully, like quitting the terminal user interface.
+#[instrument(skip(state, do_work))]
+pub(crate) async fn run(
+    state: Arc<State>,
+    do_work: impl Future<Output = Result<()>>,
+) -> Result<()> {
+    let mut events = state.subscribe();
+    let mut ctrl_c_received = false;
+
+    tokio::pin!(do_work);
+
+    loop {
+        tokio::select! {
+            result = &mut do_work => {
+                // Print the final stages, published just before the work completed.
+                while let Ok(event) = events.try_recv() {
+                    print_event(&event);
+                }
+                return result;
+            }
+
+            event = events.recv() => match event {
+                Ok(event) => print_event(&event),
+                Err(RecvError::Lagged(skipped)) => warn!("Skipped {skipped} progress events."),
+                // Never closed, as the state holds the sender.
+                Err(RecvError::Closed) => {}
+            },
+
+            result = tokio::signal::ctrl_c(), if !ctrl_c_received => {
+                result?;
+                ctrl_c_received = true;
+
+                info!("Received Ctrl+C.");
+                let state = state.clone();
+                tokio::spawn(
+                    async move {
+                        // The completion signal is of no interest, as the work future completes on its own.
+                        let (tx_shutdown_complete, rx_shutdown_complete) = oneshot::channel();
+                        if let Err(e) = state.initiate_shutdown(tx_shutdown_complete).await {
+                            error!("{e}");
+                        }
+                        drop(rx_shutdown_complete);
+                    }
+                    .in_current_span(),
+                );
+            }
+        }
+    }
+}
+
+fn print_event(event: &Event) {
+    if let Event::VideoStage { url, stage } = event {
+        println!("{stage}: {url}");
+    }
+}
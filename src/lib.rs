@@ -0,0 +1,50 @@
+#![doc = include_str!("../README.md")]
+// Opt-in to allowed-by-default rustc lints
+// Reference: https://doc.rust-lang.org/rustc/lints/groups.html
+#![warn(
+    future_incompatible,
+    let_underscore,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs,
+    // must_not_suspend, UNSTABLE: https://github.com/rust-lang/rust/issues/83310
+    non_ascii_idents,
+    nonstandard_style,
+    noop_method_call,
+    // unnameable_types, UNSTABLE: https://github.com/rust-lang/rust/issues/48054
+    unreachable_pub,
+    unused,
+    unused_crate_dependencies,
+    unused_lifetimes
+)]
+#![deny(
+    // fuzzy_provenance_casts, UNSTABLE: https://github.com/rust-lang/rust/issues/95228
+    // lossy_provenance_casts, UNSTABLE: https://github.com/rust-lang/rust/issues/95228
+    unsafe_code // Exceptions must be discussed and deemed indispensable and use `#![deny(invalid_reference_casting, unsafe_op_in_unsafe_fn)]`.
+)]
+// Opt-in to allowed-by-default clippy lints
+// Reference: https://rust-lang.github.io/rust-clippy/stable/
+#![warn(clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)] // Member of the `clippy::cargo` lint group.
+
+pub use session::{Config, Event, Session, ShutdownSignal, VideoStage};
+
+mod args;
+mod batch;
+#[doc(hidden)]
+pub mod cli;
+mod dump;
+mod error;
+mod extract;
+mod headless;
+mod metrics;
+mod notify;
+mod process;
+mod progress_log;
+mod report;
+mod session;
+mod state;
+mod trace;
+mod ui;
+mod util;
+mod webhook;
@@ -0,0 +1,49 @@
+use once_cell::sync::Lazy;
+use opentelemetry::{
+    global,
+    metrics::{Counter, UpDownCounter},
+};
+
+// Download counts, recorded via the global meter provider.
+// Unless metrics are exported, the global meter provider discards all measurements.
+struct DownloadMetrics {
+    running: UpDownCounter<i64>,
+    completed: Counter<u64>,
+    failed: Counter<u64>,
+}
+
+// Instruments are created on first use, after the meter provider has been installed.
+static DOWNLOAD_METRICS: Lazy<DownloadMetrics> = Lazy::new(|| {
+    let meter = global::meter("showcase-dl");
+
+    DownloadMetrics {
+        running: meter
+            .i64_up_down_counter("downloads.running")
+            .with_description("Number of currently running downloads")
+            .init(),
+        completed: meter
+            .u64_counter("downloads.completed")
+            .with_description("Number of completed downloads")
+            .init(),
+        failed: meter
+            .u64_counter("downloads.failed")
+            .with_description("Number of failed downloads")
+            .init(),
+    }
+});
+
+pub(crate) fn download_started() {
+    DOWNLOAD_METRICS.running.add(1, &[]);
+}
+
+pub(crate) fn download_stopped() {
+    DOWNLOAD_METRICS.running.add(-1, &[]);
+}
+
+pub(crate) fn download_completed() {
+    DOWNLOAD_METRICS.completed.add(1, &[]);
+}
+
+pub(crate) fn download_failed() {
+    DOWNLOAD_METRICS.failed.add(1, &[]);
+}
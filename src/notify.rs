@@ -0,0 +1,29 @@
+use notify_rust::Notification;
+use tracing::{info, instrument, warn};
+
+use crate::state::State;
+
+// Send a desktop notification summarizing the finished and failed downloads.
+// Failing to notify, e.g. for lack of a notification daemon, is logged but not an error.
+#[instrument(skip(state))]
+pub(crate) async fn notify_done(state: &State) {
+    let (finished, failed) = state.count_finished_and_failed().await;
+    let body = format!("{finished} downloaded, {failed} failed");
+
+    info!("Sending desktop notification: {body}");
+    let result = tokio::task::spawn_blocking(move || {
+        Notification::new()
+            .appname("showcase-dl")
+            .summary("Downloads complete")
+            .body(&body)
+            .show()
+            .map(|_| ())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("Failed sending desktop notification: {e}"),
+        Err(e) => warn!("Failed joining desktop notification task: {e}"),
+    }
+}
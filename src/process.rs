@@ -1,3 +1,6 @@
+pub(crate) mod album;
 pub(crate) mod event;
+pub(crate) mod password;
+pub(crate) mod playlist;
 pub(crate) mod showcase;
 pub(crate) mod simple_player;
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::{bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Url;
+use tracing::{debug, info, instrument, trace};
+
+use crate::{
+    process::showcase::{ClipList, ShowcaseClip},
+    state::{Extraction, State},
+};
+
+static REGEX_ALBUM_VIDEO_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"href="(?:https://vimeo\.com)?/album/\d+/video/(?P<video_id>\d+)"#).unwrap()
+});
+
+static REGEX_ALBUM_PAGE_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"href="(?P<page_url>(?:https://vimeo\.com)?/album/\d+/page:(?P<page>\d+))""#)
+        .unwrap()
+});
+
+#[instrument(skip(state))]
+pub(crate) async fn process_album(
+    album_url: &str,
+    referer: Option<&str>,
+    state: Arc<State>,
+    extraction: Extraction,
+) -> Result<()> {
+    let clips = fetch_album_clips(album_url, referer, &state).await?;
+
+    crate::process::showcase::process_clips(
+        ClipList::Album,
+        album_url,
+        clips,
+        referer,
+        state,
+        extraction,
+    )
+    .await
+}
+
+// Vimeo redirects most album pages to their showcase, listing the clips just like showcases do.
+// Legacy album pages instead link each video by its path within the album,
+// spread over pages linked as `/album/<id>/page:<n>`.
+#[instrument(skip(state))]
+async fn fetch_album_clips(
+    album_url: &str,
+    referer: Option<&str>,
+    state: &State,
+) -> Result<Vec<ShowcaseClip>> {
+    let mut page_body = crate::process::password::fetch_unlocked(album_url, referer, state).await?;
+    trace!(album_response_text = %page_body);
+
+    if let Some(clips) = crate::process::showcase::extract_clips(&page_body) {
+        return clips;
+    }
+
+    debug!("Album page lacks config. Using its video links instead.");
+    let mut clips = parse_album_video_links(&page_body);
+    let mut page = 1;
+    while let Some(page_url) = parse_album_page_link(&page_body, page + 1) {
+        page += 1;
+        let page_url = Url::parse(album_url)?.join(&page_url)?;
+        info!("Fetch album page {page} '{page_url}'...");
+        page_body =
+            crate::process::password::fetch_unlocked(page_url.as_str(), referer, state).await?;
+        trace!(album_page_response_text = %page_body);
+
+        for clip in parse_album_video_links(&page_body) {
+            if !clips.contains(&clip) {
+                clips.push(clip);
+            }
+        }
+    }
+
+    if clips.is_empty() {
+        bail!("could not find any clips in album page '{album_url}'");
+    }
+
+    Ok(clips)
+}
+
+// The link to the given page of a legacy album, if the page exists.
+fn parse_album_page_link(page_body: &str, page: u32) -> Option<String> {
+    REGEX_ALBUM_PAGE_LINK
+        .captures_iter(page_body)
+        .find(|captures| captures["page"].parse() == Ok(page))
+        .map(|captures| htmlize::unescape(&captures["page_url"]).into_owned())
+}
+
+// Each video is linked several times, e.g. by its thumbnail and its title.
+fn parse_album_video_links(page_body: &str) -> Vec<ShowcaseClip> {
+    let mut video_ids: Vec<&str> = Vec::new();
+    for captures in REGEX_ALBUM_VIDEO_LINK.captures_iter(page_body) {
+        let video_id = captures.name("video_id").unwrap().as_str();
+        if !video_ids.contains(&video_id) {
+            video_ids.push(video_id);
+        }
+    }
+
+    video_ids
+        .into_iter()
+        .map(|video_id| ShowcaseClip::Embed {
+            embed_url: format!("https://player.vimeo.com/video/{video_id}"),
+            title: None,
+            thumbnail_url: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::{fetch_album_clips, parse_album_video_links, process_album};
+    use crate::{process::showcase::ShowcaseClip, session::Config, state::State};
+
+    #[test]
+    fn album_video_links_are_parsed_once_each() {
+        let page_body = r#"
+            <a href="/album/123/video/111111111"><img src="thumb.jpg"></a>
+            <a href="/album/123/video/111111111">First Clip</a>
+            <a href="https://vimeo.com/album/123/video/222222222">Second Clip</a>
+            <a href="/album/123/page:2">Next</a>
+        "#;
+
+        assert_eq!(
+            parse_album_video_links(page_body),
+            vec![
+                ShowcaseClip::Embed {
+                    embed_url: String::from("https://player.vimeo.com/video/111111111"),
+                    title: None,
+                    thumbnail_url: None,
+                },
+                ShowcaseClip::Embed {
+                    embed_url: String::from("https://player.vimeo.com/video/222222222"),
+                    title: None,
+                    thumbnail_url: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_album_is_private() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/album/1"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/album/2"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let state = Arc::new(State::new(Config::default()));
+        for album in ["1", "2"] {
+            let album_url = format!("{}/album/{album}", server.uri());
+            let e = process_album(&album_url, None, state.clone(), state.begin_extraction())
+                .await
+                .unwrap_err();
+
+            assert!(e.to_string().contains("is private"), "{e}");
+        }
+        assert_eq!(state.videos().await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn legacy_album_pages_are_followed() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/album/123"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<a href="/album/123/video/111111111">First</a><a href="/album/123/page:2">2</a>"#,
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/album/123/page:2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<a href="/album/123/page:1">1</a><a href="/album/123/video/111111111">First</a><a href="/album/123/video/222222222">Second</a>"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let state = State::new(Config::default());
+        let clips = fetch_album_clips(&format!("{}/album/123", server.uri()), None, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            clips,
+            [
+                ShowcaseClip::Embed {
+                    embed_url: String::from("https://player.vimeo.com/video/111111111"),
+                    title: None,
+                    thumbnail_url: None,
+                },
+                ShowcaseClip::Embed {
+                    embed_url: String::from("https://player.vimeo.com/video/222222222"),
+                    title: None,
+                    thumbnail_url: None,
+                },
+            ]
+        );
+    }
+}
@@ -4,23 +4,35 @@ use color_eyre::eyre::{eyre, Result};
 use json_dotpath::DotPaths;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use reqwest::StatusCode;
 use serde_json::Value;
-use tracing::{debug, instrument, trace};
+use tracing::{debug, instrument, trace, Level};
 
-use crate::{state::State, util};
+use crate::{
+    state::{Extraction, State},
+    util,
+};
+
+// Maximum length of a response snippet included in extraction errors.
+const SNIPPET_MAX_CHARS: usize = 500;
 
 static REGEX_EVENT_URL_PARAMS: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"https://vimeo.com/event/(?P<event_id>\d+)(?:/(?P<event_hash>[\da-f]+))?").unwrap()
 });
 
 #[instrument(skip(state))]
-pub(crate) async fn process_event(event_url: &str, state: Arc<State>) -> Result<()> {
+pub(crate) async fn process_event(
+    event_url: &str,
+    state: Arc<State>,
+    extraction: Extraction,
+) -> Result<()> {
     // Assert valid event URL and extract ID and hash.
     let (event_id, maybe_event_hash) = extract_event_url_params(event_url)?;
 
     // Get event page (input URL), we need the cookie.
     // Reqwest stores the response cookie in its store, to be re-used in `get_jwt`.
-    let _response = util::fetch_with_retry(event_url, None, None).await?;
+    // Password protected events are unlocked on the way.
+    let _response_text = crate::process::password::fetch_unlocked(event_url, None, &state).await?;
 
     // Use the cookie to get a JWT.
     let jwt = get_jwt().await?;
@@ -28,9 +40,10 @@ pub(crate) async fn process_event(event_url: &str, state: Arc<State>) -> Result<
     // Use the JWT to retrieve the `clip_to_play` config URL.
     let config_url = retrieve_config_url(event_id, maybe_event_hash, &jwt).await?;
 
-    let share_url = retrieve_share_url(&config_url).await?;
+    let share_url = retrieve_share_url(&config_url, &jwt).await?;
 
-    crate::process::simple_player::process_simple_player(&share_url, None, state).await?;
+    crate::process::simple_player::process_simple_player(&share_url, None, state, extraction)
+        .await?;
 
     Ok(())
 }
@@ -63,9 +76,12 @@ async fn get_jwt() -> Result<String> {
     let response_json: Value = serde_json::from_str(&response_text)?;
     debug!("JWT response data: {response_json:#?}");
 
-    let jwt = response_json
-        .dot_get::<String>("jwt")?
-        .ok_or_else(|| eyre!("could not extract JWT from event viewer data"))?;
+    let jwt = response_json.dot_get::<String>("jwt")?.ok_or_else(|| {
+        eyre!(
+            "could not extract JWT from event viewer data ({})",
+            describe_json(&response_json)
+        )
+    })?;
     debug!("JWT: {jwt:#?}");
 
     Ok(jwt)
@@ -101,7 +117,8 @@ async fn retrieve_config_url(
         .dot_get::<String>("clip_to_play.config_url")?
         .ok_or_else(|| {
             eyre!(
-                "could not extract video config URL 'clip_to_play.config_url' from live event data"
+                "could not extract video config URL 'clip_to_play.config_url' from live event data ({})",
+                describe_json(&response_json)
             )
         })?;
     debug!("Config URL: {config_url:#?}");
@@ -110,11 +127,23 @@ async fn retrieve_config_url(
 }
 
 #[instrument]
-async fn retrieve_share_url(config_url: &str) -> Result<String> {
-    let response_text = util::fetch_with_retry(config_url, None, None)
-        .await?
-        .text()
-        .await?;
+async fn retrieve_share_url(config_url: &str, jwt: &str) -> Result<String> {
+    // Public events serve their config without authorization.
+    let mut response = util::fetch_with_retry(config_url, None, None).await?;
+
+    // Gated events require the viewer's JWT for the config as well.
+    if matches!(
+        response.status(),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+    ) {
+        debug!(
+            "Config fetch was refused with status {}, retrying with JWT.",
+            response.status()
+        );
+        response = util::fetch_with_retry(config_url, None, Some(&format!("jwt {jwt}"))).await?;
+    }
+
+    let response_text = response.text().await?;
     trace!(config_response_text = %response_text);
 
     // Parsing in a separate step for easier JSON decode debugging.
@@ -124,9 +153,61 @@ async fn retrieve_share_url(config_url: &str) -> Result<String> {
     let share_url = response_json
         .dot_get::<String>("video.share_url")?
         .ok_or_else(|| {
-            eyre!("could not extract video share URL 'video.share_url' from config data")
+            eyre!(
+                "could not extract video share URL 'video.share_url' from config data ({})",
+                describe_json(&response_json)
+            )
         })?;
     debug!("Config URL: {share_url:#?}");
 
     Ok(share_url)
 }
+
+// Describe the shape of a response which lacks an expected value, to speed up diagnosing schema changes.
+// The top-level keys are always listed, while a truncated snippet of the response is only included
+// at debug verbosity, as it may be long and contain personal data.
+fn describe_json(value: &Value) -> String {
+    let shape = match value {
+        Value::Object(map) => format!(
+            "top-level keys: [{}]",
+            map.keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Array(array) => format!("top-level array of {} items", array.len()),
+        _ => String::from("not a JSON object"),
+    };
+
+    if !tracing::enabled!(Level::DEBUG) {
+        return format!("{shape}; raise verbosity to -vvv to include a snippet of the response");
+    }
+
+    let json = value.to_string();
+    let snippet: String = json.chars().take(SNIPPET_MAX_CHARS).collect();
+    if snippet.len() < json.len() {
+        format!("{shape}; response: {snippet}...")
+    } else {
+        format!("{shape}; response: {snippet}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::describe_json;
+
+    #[test]
+    fn describes_top_level_keys() {
+        let description = describe_json(&json!({ "data": { "jwt": null }, "user": 1 }));
+
+        assert!(description.starts_with("top-level keys: [data, user]"));
+    }
+
+    #[test]
+    fn describes_non_objects() {
+        assert!(describe_json(&json!([1, 2])).starts_with("top-level array of 2 items"));
+        assert!(describe_json(&json!("text")).starts_with("not a JSON object"));
+    }
+}
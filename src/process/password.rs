@@ -0,0 +1,134 @@
+use color_eyre::eyre::{bail, eyre, Result};
+use json_dotpath::DotPaths;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::StatusCode;
+use serde_json::Value;
+use tracing::{debug, info, instrument, trace};
+
+use crate::{state::State, util};
+
+static REGEX_PASSWORD_GATE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:<input[^>]+name="password"|"view"\s*:\s*"password")"#).unwrap());
+
+static REGEX_AUTH_BASE_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?P<base_url>https://vimeo\.com/(?:showcase|album|event)/\d+)").unwrap()
+});
+
+// Fetch the page at the given URL, unlocking it with `--video-password` if it is password protected.
+#[instrument(skip(state))]
+pub(crate) async fn fetch_unlocked(
+    page_url: &str,
+    referer: Option<&str>,
+    state: &State,
+) -> Result<String> {
+    let response = util::fetch_with_retry(page_url, referer, None).await?;
+    debug!(page_response.status = %response.status());
+
+    // Private pages are refused outright, while password protected pages serve a password gate.
+    if is_private_status(response.status()) {
+        bail!("'{page_url}' is private (hint: only public and password protected pages can be downloaded, the latter via the '--video-password' option)");
+    }
+
+    let response_text = response.text().await?;
+    trace!(page_response_text = %response_text);
+
+    if !is_password_gate(&response_text) {
+        return Ok(response_text);
+    }
+
+    let Some(password) = state.video_password.as_deref() else {
+        bail!("'{page_url}' is password protected (hint: pass the password via the '--video-password' option)");
+    };
+
+    info!("Unlock password protected page '{page_url}'...");
+    submit_password(page_url, referer, password).await?;
+
+    // The unlocked session is kept in the client's cookie store.
+    let response_text = util::fetch_with_retry(page_url, referer, None)
+        .await?
+        .text()
+        .await?;
+    trace!(unlocked_page_response_text = %response_text);
+
+    if is_password_gate(&response_text) {
+        bail!("'{page_url}' is still password protected after submitting the password (hint: check the '--video-password' option)");
+    }
+
+    Ok(response_text)
+}
+
+fn is_private_status(status: StatusCode) -> bool {
+    matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+}
+
+fn is_password_gate(page_body: &str) -> bool {
+    REGEX_PASSWORD_GATE.is_match(page_body)
+}
+
+#[instrument(skip(password))]
+async fn submit_password(page_url: &str, referer: Option<&str>, password: &str) -> Result<()> {
+    let auth_url = format!(
+        "{}/auth",
+        REGEX_AUTH_BASE_URL
+            .captures(page_url)
+            .and_then(|captures| captures.name("base_url"))
+            .ok_or_else(|| eyre!(
+                "can not unlock '{page_url}', as it is neither a Vimeo showcase, album nor event URL"
+            ))?
+            .as_str()
+    );
+
+    let token = get_xsrf_token().await?;
+
+    let response = util::post_form_with_retry(
+        &auth_url,
+        referer,
+        &[("password", password), ("token", &token)],
+    )
+    .await?;
+    debug!(auth_response.status = %response.status());
+
+    if !response.status().is_success() {
+        bail!(
+            "password submission to '{auth_url}' failed with status {}",
+            response.status()
+        );
+    }
+
+    Ok(())
+}
+
+#[instrument]
+async fn get_xsrf_token() -> Result<String> {
+    let response_text = util::fetch_with_retry("https://vimeo.com/_next/viewer", None, None)
+        .await?
+        .text()
+        .await?;
+    trace!(viewer_response_text = %response_text);
+
+    // Parsing in a separate step for easier JSON decode debugging.
+    let response_json: Value = serde_json::from_str(&response_text)?;
+    debug!("viewer response data: {response_json:#?}");
+
+    let token = response_json
+        .dot_get::<String>("xsrft")?
+        .ok_or_else(|| eyre!("could not extract XSRF token 'xsrft' from viewer data"))?;
+
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::is_private_status;
+
+    #[test]
+    fn refused_page_is_private() {
+        assert!(is_private_status(StatusCode::FORBIDDEN));
+        assert!(is_private_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_private_status(StatusCode::OK));
+        assert!(!is_private_status(StatusCode::NOT_FOUND));
+    }
+}
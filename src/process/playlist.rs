@@ -0,0 +1,262 @@
+use std::{process::Stdio, sync::Arc};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use futures::{stream, TryStreamExt};
+use json_dotpath::DotPaths;
+use serde_json::Value;
+use tracing::{debug, info, instrument, trace, Instrument};
+
+use crate::state::{video::Video, Extraction, State};
+
+struct PlaylistEntry {
+    url: String,
+    title: Option<String>,
+}
+
+#[instrument(skip(state))]
+pub(crate) async fn process_playlist(
+    playlist_url: &str,
+    referer: Option<&str>,
+    state: Arc<State>,
+    extraction: Extraction,
+) -> Result<()> {
+    info!("Enumerate playlist entries...");
+    let entries = enumerate_playlist_entries(playlist_url, referer, &state).await?;
+
+    if entries.len() <= 1 {
+        debug!(
+            "Playlist '{playlist_url}' yielded {} entries, falling back to single video.",
+            entries.len()
+        );
+        return crate::process::simple_player::process_simple_player(
+            playlist_url,
+            referer,
+            state,
+            extraction,
+        )
+        .await;
+    }
+
+    info!("Download {} playlist entries...", entries.len());
+    let remaining_video_slots = state.remaining_video_slots().await;
+    let entries = extraction.branch(entries.into_iter().take(remaining_video_slots));
+    stream::iter(entries.into_iter().map(Ok))
+        .try_for_each_concurrent(None, |(entry, extraction)| async {
+            let state = state.clone();
+            let referer = referer.map(ToOwned::to_owned);
+            tokio::spawn(
+                async move {
+                    let video = Arc::new(
+                        Video::from_url(entry.url)
+                            .referer(referer)
+                            .title(entry.title)
+                            .build(),
+                    );
+                    if !(*state).push_video(video.clone()).await {
+                        return Ok(());
+                    }
+                    drop(extraction);
+
+                    info!("Download playlist entry '{}'...", video.url());
+                    video.download(state).await
+                }
+                .in_current_span(),
+            )
+            .await?
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[instrument(skip(state))]
+async fn enumerate_playlist_entries(
+    playlist_url: &str,
+    referer: Option<&str>,
+    state: &State,
+) -> Result<Vec<PlaylistEntry>> {
+    let mut command = state.downloader_request_command(referer);
+
+    command
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("--flat-playlist")
+        .arg("--dump-json");
+
+    let output = command
+        .arg(playlist_url)
+        .output()
+        .await
+        .wrap_err_with(|| format!("Could not enumerate playlist '{playlist_url}'"))?;
+
+    if !output.status.success() {
+        bail!(
+            "Downloader failed to enumerate playlist '{playlist_url}': {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    trace!(playlist_dump = %stdout);
+
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            // Parsing in a separate step for easier JSON decode debugging.
+            let entry: Value = serde_json::from_str(line)?;
+            debug!("playlist entry data: {entry:#?}");
+
+            let url = entry
+                .dot_get::<String>("url")?
+                .or(entry.dot_get::<String>("webpage_url")?)
+                .ok_or_else(|| eyre!("could not extract 'url' from playlist entry"))?;
+
+            Ok(PlaylistEntry {
+                url,
+                title: entry.dot_get::<String>("title")?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::process_playlist;
+    use crate::{
+        session::Config,
+        state::{video::downloader::ScriptedDownloader, State},
+    };
+
+    const PLAYLIST_URL: &str = "https://www.youtube.com/playlist?list=PL123";
+
+    // Enumerate the scripted playlist entries, without downloading them.
+    async fn process_scripted_playlist(
+        playlist_url: &str,
+        lines: Vec<&'static str>,
+        video_limit: Option<usize>,
+    ) -> Arc<State> {
+        let state = Arc::new(
+            State::new(Config {
+                extract_only: true,
+                video_limit,
+                ..Config::default()
+            })
+            .with_downloader_command(ScriptedDownloader {
+                lines,
+                exit_code: Some(0),
+            }),
+        );
+        let extraction = state.begin_extraction();
+
+        process_playlist(playlist_url, None, state.clone(), extraction)
+            .await
+            .unwrap();
+
+        state
+    }
+
+    async fn video_urls_and_titles(state: &State) -> Vec<(String, Option<String>)> {
+        let mut videos = Vec::new();
+        for video in state.videos().await.iter() {
+            videos.push((video.url().to_owned(), video.title().await.clone()));
+        }
+        videos.sort();
+        videos
+    }
+
+    #[tokio::test]
+    async fn entries_are_expanded_into_videos() {
+        let state = process_scripted_playlist(
+            PLAYLIST_URL,
+            vec![
+                r#"{"url": "https://www.youtube.com/watch?v=a", "title": "First"}"#,
+                "",
+                r#"{"url": "https://www.youtube.com/watch?v=b", "title": "Second"}"#,
+            ],
+            None,
+        )
+        .await;
+
+        assert_eq!(
+            video_urls_and_titles(&state).await,
+            [
+                (
+                    String::from("https://www.youtube.com/watch?v=a"),
+                    Some(String::from("First"))
+                ),
+                (
+                    String::from("https://www.youtube.com/watch?v=b"),
+                    Some(String::from("Second"))
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn webpage_url_is_used_without_url() {
+        let state = process_scripted_playlist(
+            PLAYLIST_URL,
+            vec![
+                r#"{"webpage_url": "https://www.youtube.com/watch?v=a"}"#,
+                r#"{"url": "https://www.youtube.com/watch?v=b", "webpage_url": "https://www.youtube.com/watch?v=c"}"#,
+            ],
+            None,
+        )
+        .await;
+
+        assert_eq!(
+            video_urls_and_titles(&state).await,
+            [
+                (String::from("https://www.youtube.com/watch?v=a"), None),
+                (String::from("https://www.youtube.com/watch?v=b"), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn single_entry_falls_back_to_single_video() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("<html><head><title>My Video</title></head></html>"),
+            )
+            .mount(&server)
+            .await;
+        let playlist_url = format!("{}/watch?v=a&list=PL123", server.uri());
+
+        let state = process_scripted_playlist(
+            &playlist_url,
+            vec![r#"{"url": "https://www.youtube.com/watch?v=a"}"#],
+            None,
+        )
+        .await;
+
+        assert_eq!(
+            video_urls_and_titles(&state).await,
+            [(playlist_url, Some(String::from("My Video")))]
+        );
+    }
+
+    #[tokio::test]
+    async fn video_limit_caps_expanded_entries() {
+        let state = process_scripted_playlist(
+            PLAYLIST_URL,
+            vec![
+                r#"{"url": "https://www.youtube.com/watch?v=a"}"#,
+                r#"{"url": "https://www.youtube.com/watch?v=b"}"#,
+                r#"{"url": "https://www.youtube.com/watch?v=c"}"#,
+            ],
+            Some(2),
+        )
+        .await;
+
+        assert_eq!(state.videos().await.len(), 2);
+    }
+}
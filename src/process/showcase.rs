@@ -5,19 +5,17 @@ use futures::{stream, TryStreamExt};
 use json_dotpath::DotPaths;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use reqwest::Url;
 
-use serde_json::Value;
-use tracing::{debug, info, instrument, trace, Instrument};
+use serde_json::{Map, Value};
+use tracing::{debug, info, instrument, trace, warn, Instrument};
 
 use crate::{
-    state::{video::Video, State},
+    state::{video::Video, Extraction, State},
     util,
 };
 
-static REGEX_SHOWCASE_IFRAME: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"<iframe[^>]* (?:data-)?src="(?P<embed_url>https://vimeo\.com/showcase/[^"]+)""#)
-        .unwrap()
-});
+const SHOWCASE_URL_PREFIX: &str = "https://vimeo.com/showcase/";
 
 static REGEX_EMBED_URL: Lazy<Regex> =
     Lazy::new(|| Regex::new(r#"src="(?P<embed_url>[^"]+)""#).unwrap());
@@ -25,26 +23,73 @@ static REGEX_EMBED_URL: Lazy<Regex> =
 static REGEX_SHOWCASE_CONFIG: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"dataForPlayer = (?P<showcase_config>\{.*?\});").unwrap());
 
+static REGEX_SHOWCASE_LINKED_DATA: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?s)<script type="application/ld\+json">(?P<linked_data>.*?)</script>"#).unwrap()
+});
+
+// A showcase clip, either as listed in the player config, or by its embed URL,
+// as listed in the page's JSON-LD `ItemList` or an album's video links.
+// JSON-LD list elements lacking an embed URL are kept as `Malformed`, to be skipped with a notice.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ShowcaseClip {
+    Config(Value),
+    Embed {
+        embed_url: String,
+        title: Option<String>,
+        thumbnail_url: Option<String>,
+    },
+    Malformed(String),
+}
+
+// Lists of clips downloaded through `process_clips`, named in log messages and notices.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ClipList {
+    Showcase,
+    Album,
+}
+
+impl ClipList {
+    fn name(self) -> &'static str {
+        match self {
+            ClipList::Showcase => "showcase",
+            ClipList::Album => "album",
+        }
+    }
+
+    fn capitalized_name(self) -> &'static str {
+        match self {
+            ClipList::Showcase => "Showcase",
+            ClipList::Album => "Album",
+        }
+    }
+}
+
 #[instrument(skip(page_body, state))]
 pub(crate) async fn process_showcases(
     page_body: &str,
     referer: Option<&str>,
     state: Arc<State>,
+    extraction: Extraction,
 ) -> Result<()> {
-    stream::iter(REGEX_SHOWCASE_IFRAME.captures_iter(page_body).map(Ok))
-        .try_for_each_concurrent(None, |captures| {
+    let embed_urls = crate::extract::iframe::iframe_sources(page_body, |source| {
+        source.len() > SHOWCASE_URL_PREFIX.len() && source.starts_with(SHOWCASE_URL_PREFIX)
+    });
+
+    stream::iter(extraction.branch(embed_urls).into_iter().map(Ok))
+        .try_for_each_concurrent(None, |(embed_url, extraction)| {
             let state = state.clone();
             async move {
-                debug!("{captures:#?}");
-
-                match captures.name("embed_url") {
-                    Some(embed_url_match) => {
-                        let embed_url = htmlize::unescape_attribute(embed_url_match.as_str());
-                        info!("Extract clips from showcase '{embed_url}'...");
-                        process_showcase(embed_url.as_ref(), referer, state).await
-                    }
-                    None => bail!("Capture group did not match named 'embed_url'"),
+                if !state.is_embed_wanted(&embed_url) {
+                    debug!("Skipping unwanted showcase '{embed_url}'.");
+                    return Ok(());
                 }
+
+                // Each showcase embed carries its own referer, just like simple player embeds.
+                let embed_referer =
+                    crate::extract::embeds::embed_referer(&Url::parse(&embed_url)?, referer);
+
+                info!("Extract clips from showcase '{embed_url}'...");
+                process_showcase(&embed_url, embed_referer.as_deref(), state, extraction).await
             }
         })
         .await?;
@@ -57,51 +102,256 @@ pub(crate) async fn process_showcase(
     showcase_url: &str,
     referer: Option<&str>,
     state: Arc<State>,
+    extraction: Extraction,
 ) -> Result<()> {
-    let response_text = util::fetch_with_retry(showcase_url, referer, None)
-        .await?
-        .text()
+    let clips = fetch_showcase_clips(showcase_url, referer, &state).await?;
+
+    // Some showcase clips only play when referred to by their showcase, rather than by the embedding page.
+    let clip_referer = if state.referer_from_showcase {
+        Some(showcase_url)
+    } else {
+        referer
+    };
+
+    process_clips(
+        ClipList::Showcase,
+        showcase_url,
+        clips,
+        clip_referer,
+        state,
+        extraction,
+    )
+    .await
+}
+
+// Push and download a video per clip, shared by showcases and albums.
+#[instrument(skip(clips, state, extraction))]
+pub(crate) async fn process_clips(
+    list: ClipList,
+    list_url: &str,
+    clips: Vec<ShowcaseClip>,
+    clip_referer: Option<&str>,
+    state: Arc<State>,
+    extraction: Extraction,
+) -> Result<()> {
+    // A valid, but empty list would otherwise end the run without any sign of what happened.
+    if clips.is_empty() {
+        let name = list.capitalized_name();
+        warn!("{name} '{list_url}' contained no clips.");
+        state.push_notice(format!("{name} '{list_url}' contained no clips"));
+        return Ok(());
+    }
+
+    // Only the first clips are processed if the video limit is about to be reached.
+    let remaining_video_slots = state.remaining_video_slots().await;
+    if clips.len() > remaining_video_slots {
+        info!(
+            "Limiting {} '{list_url}' to its first {remaining_video_slots} of {} clips.",
+            list.name(),
+            clips.len()
+        );
+    }
+
+    let clips = extraction.branch(clips.into_iter().take(remaining_video_slots));
+    stream::iter(clips.into_iter().map(Ok))
+        .try_for_each_concurrent(None, |(clip, extraction)| async {
+            let state = state.clone();
+            let referer = clip_referer.map(ToOwned::to_owned);
+            let list_url = list_url.to_owned();
+            tokio::spawn(
+                async move { process_clip(list, &list_url, &clip, referer, state, extraction).await }
+                    .in_current_span(),
+            )
+            .await?
+        })
         .await?;
-    trace!(showcase_response_text = %response_text);
 
-    let maybe_captures = REGEX_SHOWCASE_CONFIG.captures(&response_text);
+    Ok(())
+}
+
+// Fetch the showcase page and extract its clips from the player config JSON,
+// falling back to the JSON-LD `ItemList` which Vimeo serves instead on some pages.
+// Vimeo occasionally serves the page shell before hydration, lacking either,
+// in which case the page is fetched again, with the delays and retry count of failed requests.
+#[instrument(skip(state))]
+async fn fetch_showcase_clips(
+    showcase_url: &str,
+    referer: Option<&str>,
+    state: &State,
+) -> Result<Vec<ShowcaseClip>> {
+    let mut retries_remaining = util::REQUEST_RETRIES;
+    loop {
+        let response_text =
+            crate::process::password::fetch_unlocked(showcase_url, referer, state).await?;
+        trace!(showcase_response_text = %response_text);
+
+        if let Some(clips) = extract_clips(&response_text) {
+            return clips;
+        }
 
-    if let Some(captures) = maybe_captures {
-        if let Some(showcase_config) = captures.name("showcase_config") {
-            debug!(
-                "Parsing showcase config JSON: {:#?}",
-                showcase_config.as_str()
+        if retries_remaining == 0 {
+            bail!(
+                "could not find showcase config 'dataForPlayer' in showcase page '{showcase_url}'"
             );
-            let data: Value = serde_json::from_str(showcase_config.as_str())?;
-            debug!(decoded_showcase_config = ?data);
-
-            // Query for `{ "clips": [...] }` array
-            let clips = data.dot_get::<Vec<Value>>("clips")?.ok_or_else(|| {
-                eyre!("could not find 'clips' key in 'dataForPlayer', or 'clips' was not an array (hint: if you are passing a Vimeo URL, then try providing the embedding page URL via the '--referer' option)")
-            })?;
-            stream::iter(clips.into_iter().map(Ok))
-                .try_for_each_concurrent(None, |clip| async {
-                    let state = state.clone();
-                    let referer = referer.map(ToOwned::to_owned);
-                    tokio::spawn(
-                        async move { process_showcase_clip(&clip, referer, state).await }
-                            .in_current_span(),
-                    )
-                    .await?
-                })
-                .await?;
         }
+        retries_remaining -= 1;
+
+        let delay = util::retry_delay(util::REQUEST_RETRIES - retries_remaining);
+        warn!(%showcase_url, ?delay, "Showcase page lacks config and clip list. Retrying after delay. ({retries_remaining} further retries remaining)");
+        tokio::time::sleep(delay).await;
     }
+}
 
-    Ok(())
+// Extract the clips from a showcase-style page, if it lists any.
+pub(crate) fn extract_clips(page_body: &str) -> Option<Result<Vec<ShowcaseClip>>> {
+    if let Some(showcase_config) = REGEX_SHOWCASE_CONFIG
+        .captures(page_body)
+        .and_then(|captures| captures.name("showcase_config"))
+    {
+        return Some(parse_showcase_config(showcase_config.as_str()));
+    }
+
+    let clips = parse_linked_data_clips(page_body)?;
+    debug!("Page lacks config. Using its JSON-LD item list instead.");
+    Some(Ok(clips))
 }
 
-#[instrument(skip(state))]
-async fn process_showcase_clip(
-    clip: &Value,
+// Parse the `dataForPlayer` config JSON, listing the clips' config URLs.
+fn parse_showcase_config(showcase_config: &str) -> Result<Vec<ShowcaseClip>> {
+    debug!("Parsing showcase config JSON: {showcase_config:#?}");
+    let data: Value = serde_json::from_str(showcase_config)?;
+    debug!(decoded_showcase_config = ?data);
+
+    // Query for `{ "clips": [...] }` array
+    let clips = data.dot_get::<Vec<Value>>("clips")?.ok_or_else(|| {
+        eyre!("could not find 'clips' key in 'dataForPlayer', or 'clips' was not an array (hint: if you are passing a Vimeo URL, then try providing the embedding page URL via the '--referer' option)")
+    })?;
+
+    Ok(clips.into_iter().map(ShowcaseClip::Config).collect())
+}
+
+// Find the clips in the first schema.org `ItemList` among the page's JSON-LD scripts.
+// The list may be top-level, inside an array, or wrapped in `@graph`,
+// with either an `http` or an `https` `@context`.
+fn parse_linked_data_clips(page_body: &str) -> Option<Vec<ShowcaseClip>> {
+    REGEX_SHOWCASE_LINKED_DATA
+        .captures_iter(page_body)
+        .filter_map(|captures| serde_json::from_str::<Value>(&captures["linked_data"]).ok())
+        .find_map(|linked_data| {
+            find_item_list(&linked_data, None)
+                .map(|elements| elements.iter().map(parse_linked_data_clip).collect())
+        })
+}
+
+fn find_item_list<'a>(value: &'a Value, context: Option<&'a str>) -> Option<&'a Vec<Value>> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .find_map(|value| find_item_list(value, context)),
+        Value::Object(object) => {
+            // The `@context` is inherited from enclosing objects, such as a `@graph` wrapper.
+            let context = object.get("@context").and_then(Value::as_str).or(context);
+
+            if let Some(graph) = object.get("@graph") {
+                return find_item_list(graph, context);
+            }
+
+            if object.get("@type").and_then(Value::as_str) != Some("ItemList")
+                || !context.is_some_and(is_schema_org_context)
+            {
+                return None;
+            }
+
+            object.get("itemListElement").and_then(Value::as_array)
+        }
+        _ => None,
+    }
+}
+
+fn is_schema_org_context(context: &str) -> bool {
+    matches!(
+        context.trim_end_matches('/'),
+        "http://schema.org" | "https://schema.org"
+    )
+}
+
+// List elements are either `ListItem`s wrapping a `VideoObject` as `item`, or `VideoObject`s themselves.
+fn parse_linked_data_clip(element: &Value) -> ShowcaseClip {
+    let video_object = element.get("item").unwrap_or(element);
+    let Some(embed_url) = ["embedUrl", "url"]
+        .iter()
+        .find_map(|key| video_object.get(key)?.as_str())
+    else {
+        return ShowcaseClip::Malformed(String::from(
+            "could not find 'embedUrl' or 'url' of JSON-LD item list element",
+        ));
+    };
+    let title = video_object
+        .get("name")
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned);
+    // The thumbnail URL may be given as a single URL, or as a list of URLs.
+    let thumbnail_url = video_object
+        .get("thumbnailUrl")
+        .and_then(|thumbnail_url| match thumbnail_url {
+            Value::Array(thumbnail_urls) => thumbnail_urls.first(),
+            thumbnail_url => Some(thumbnail_url),
+        })
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned);
+
+    ShowcaseClip::Embed {
+        embed_url: embed_url.to_owned(),
+        title,
+        thumbnail_url,
+    }
+}
+
+#[instrument(skip(state, extraction))]
+async fn process_clip(
+    list: ClipList,
+    list_url: &str,
+    clip: &ShowcaseClip,
     referer: Option<String>,
     state: Arc<State>,
+    extraction: Extraction,
 ) -> Result<()> {
+    let video = match clip {
+        ShowcaseClip::Config(clip) => fetch_clip_video(clip, referer).await,
+        ShowcaseClip::Embed {
+            embed_url,
+            title,
+            thumbnail_url,
+        } => Ok(
+            Video::new_with_title(embed_url.as_str(), referer, title.clone())
+                .with_thumbnail_url(thumbnail_url.clone()),
+        ),
+        ShowcaseClip::Malformed(reason) => Err(eyre!("{reason}")),
+    };
+
+    let video = match video {
+        Ok(video) => video,
+        // A single malformed or unavailable clip must not fail its sibling clips.
+        Err(e) => {
+            let name = list.name();
+            warn!("Skipping clip of {name} '{list_url}': {e:?}");
+            state.push_notice(format!("Skipped a clip of {name} '{list_url}': {e}"));
+            return Ok(());
+        }
+    };
+
+    let video = Arc::new(video);
+    if !(*state).push_video(video.clone()).await {
+        return Ok(());
+    }
+    drop(extraction);
+
+    info!("Download {} clip '{}'...", list.name(), video.url());
+    video.download(state).await
+}
+
+// Fetch a clip's player config, and create its video from the config's embed URL, title and thumbnail URL.
+async fn fetch_clip_video(clip: &Value, referer: Option<String>) -> Result<Video> {
     let config_url = clip.dot_get::<String>("config")?.ok_or_else(|| {
         eyre!("could not read clip config URL from 'dataForPlayer.clips.[].config'")
     })?;
@@ -116,38 +366,281 @@ async fn process_showcase_clip(
 
     debug!("config response data: {config:#?}");
 
+    let ClipConfig {
+        embed_url,
+        title,
+        thumbnail_url,
+    } = parse_clip_config(&config)?;
+
+    // Clips without title are displayed by their URL.
+    Ok(Video::new_with_title(embed_url.as_str(), referer, title).with_thumbnail_url(thumbnail_url))
+}
+
+#[derive(Debug)]
+struct ClipConfig {
+    embed_url: String,
+    title: Option<String>,
+    thumbnail_url: Option<String>,
+}
+
+// Extract the embed URL and - if present - the title and thumbnail URL from a clip's config.
+fn parse_clip_config(config: &Value) -> Result<ClipConfig> {
     let embed_code = config
         .dot_get::<String>("video.embed_code")?
         .ok_or_else(|| eyre!("could not extract clip embed code 'video.embed_code' from config"))?;
 
     debug!("config embed_code: {embed_code}");
 
-    let captures = REGEX_EMBED_URL.captures(&embed_code).ok_or_else(|| {
-        eyre!(
-            "could not extract embed URL from config 'video.embed_code' string (no regex captures)"
+    let embed_url_match = REGEX_EMBED_URL
+        .captures(&embed_code)
+        .and_then(|captures| captures.name("embed_url"))
+        .ok_or_else(|| {
+            eyre!("could not extract embed URL from config 'video.embed_code' string")
+        })?;
+
+    debug!("embed_url_match: {embed_url_match:#?}");
+
+    let embed_url = htmlize::unescape_attribute(embed_url_match.as_str()).into_owned();
+
+    // A missing or non-string title is no reason to skip the clip.
+    let title = config.dot_get::<String>("video.title").ok().flatten();
+
+    Ok(ClipConfig {
+        embed_url,
+        title,
+        thumbnail_url: thumbnail_url(config),
+    })
+}
+
+// Select the largest thumbnail from `video.thumbs`, which maps widths - and `base` - to image URLs.
+fn thumbnail_url(config: &Value) -> Option<String> {
+    let thumbs = config
+        .dot_get::<Map<String, Value>>("video.thumbs")
+        .ok()??;
+
+    thumbs
+        .iter()
+        .filter_map(|(width, url)| Some((width.parse::<u32>().ok()?, url.as_str()?)))
+        .max_by_key(|(width, _)| *width)
+        .map(|(_, url)| url)
+        .or_else(|| thumbs.get("base")?.as_str())
+        .map(ToOwned::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::{
+        fetch_showcase_clips, parse_clip_config, parse_linked_data_clips, process_clips, ClipList,
+        ShowcaseClip,
+    };
+    use crate::{session::Config, state::State};
+
+    fn expected_linked_data_clips() -> Vec<ShowcaseClip> {
+        vec![
+            ShowcaseClip::Embed {
+                embed_url: String::from("https://player.vimeo.com/video/111111111"),
+                title: Some(String::from("First Clip")),
+                thumbnail_url: Some(String::from("https://i.vimeocdn.com/video/111111111-d_640")),
+            },
+            ShowcaseClip::Embed {
+                embed_url: String::from("https://player.vimeo.com/video/222222222"),
+                title: Some(String::from("Second Clip")),
+                thumbnail_url: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn linked_data_item_list_is_parsed() {
+        let page_body = include_str!("fixtures/showcase_item_list.html");
+
+        assert_eq!(
+            parse_linked_data_clips(page_body),
+            Some(expected_linked_data_clips())
+        );
+    }
+
+    #[test]
+    fn linked_data_graph_item_list_is_parsed() {
+        let page_body = include_str!("fixtures/showcase_graph_item_list.html");
+
+        assert_eq!(
+            parse_linked_data_clips(page_body),
+            Some(expected_linked_data_clips())
+        );
+    }
+
+    #[test]
+    fn linked_data_element_without_url_is_malformed() {
+        let page_body = r#"<script type="application/ld+json">{"@context":"https://schema.org","@type":"ItemList","itemListElement":[{"@type":"VideoObject","name":"No URL"}]}</script>"#;
+
+        assert!(matches!(
+            parse_linked_data_clips(page_body).as_deref(),
+            Some([ShowcaseClip::Malformed(_)])
+        ));
+    }
+
+    #[test]
+    fn linked_data_without_item_list_is_ignored() {
+        let page_body = r#"<script type="application/ld+json">{"@context":"https://schema.org","@type":"WebPage","name":"Showcase"}</script>"#;
+
+        assert_eq!(parse_linked_data_clips(page_body), None);
+    }
+
+    #[test]
+    fn clip_without_title_is_kept() {
+        let config = json!({
+            "video": {
+                "embed_code": r#"<iframe src="https://player.vimeo.com/video/12345?h=abcdef&amp;app_id=1"></iframe>"#,
+            },
+        });
+
+        let clip_config = parse_clip_config(&config).unwrap();
+
+        assert_eq!(
+            clip_config.embed_url,
+            "https://player.vimeo.com/video/12345?h=abcdef&app_id=1"
+        );
+        assert_eq!(clip_config.title, None);
+        assert_eq!(clip_config.thumbnail_url, None);
+    }
+
+    #[test]
+    fn largest_thumbnail_is_selected() {
+        let config = json!({
+            "video": {
+                "embed_code": r#"<iframe src="https://player.vimeo.com/video/12345"></iframe>"#,
+                "thumbs": {
+                    "640": "https://i.vimeocdn.com/video/1-d_640",
+                    "1280": "https://i.vimeocdn.com/video/1-d_1280",
+                    "960": "https://i.vimeocdn.com/video/1-d_960",
+                    "base": "https://i.vimeocdn.com/video/1-d",
+                },
+            },
+        });
+
+        assert_eq!(
+            parse_clip_config(&config).unwrap().thumbnail_url.as_deref(),
+            Some("https://i.vimeocdn.com/video/1-d_1280")
+        );
+    }
+
+    #[test]
+    fn clip_without_embed_code_is_rejected() {
+        let config = json!({
+            "video": {
+                "title": "My Video",
+            },
+        });
+
+        assert!(parse_clip_config(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn malformed_clip_config_skips_only_its_clip() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("not a config"))
+            .mount(&server)
+            .await;
+
+        let state = Arc::new(State::new(Config {
+            extract_only: true,
+            ..Config::default()
+        }));
+        let clips = vec![
+            ShowcaseClip::Config(json!({ "config": format!("{}/config", server.uri()) })),
+            ShowcaseClip::Malformed(String::from("could not find 'embedUrl' or 'url'")),
+            ShowcaseClip::Embed {
+                embed_url: String::from("https://player.vimeo.com/video/222222222"),
+                title: None,
+                thumbnail_url: None,
+            },
+        ];
+        let extraction = state.begin_extraction();
+
+        process_clips(
+            ClipList::Showcase,
+            "https://vimeo.com/showcase/123",
+            clips,
+            None,
+            state.clone(),
+            extraction,
         )
-    })?;
+        .await
+        .unwrap();
 
-    match captures.name("embed_url") {
-        Some(embed_url_match) => {
-            debug!("embed_url_match: {embed_url_match:#?}");
+        assert_eq!(state.notices().len(), 2);
+        assert_eq!(
+            state.video(1).await.unwrap().url(),
+            "https://player.vimeo.com/video/222222222"
+        );
+        assert!(state.video(2).await.is_none());
+    }
 
-            let embed_url = htmlize::unescape_attribute(embed_url_match.as_str());
+    #[tokio::test]
+    async fn clip_download_errors_are_not_skipped() {
+        let state = Arc::new(State::new(Config {
+            downloader: String::from("/nonexistent/downloader"),
+            ..Config::default()
+        }));
+        let clips = vec![ShowcaseClip::Embed {
+            embed_url: String::from("https://player.vimeo.com/video/111111111"),
+            title: None,
+            thumbnail_url: None,
+        }];
+        let extraction = state.begin_extraction();
 
-            let video = Arc::new(Video::new_with_title(
-                embed_url.as_ref(),
-                referer,
-                config.dot_get::<String>("video.title")?,
-            ));
-            (*state).push_video(video.clone()).await;
+        let result = process_clips(
+            ClipList::Showcase,
+            "https://vimeo.com/showcase/123",
+            clips,
+            None,
+            state.clone(),
+            extraction,
+        )
+        .await;
 
-            info!("Download showcase clip '{embed_url}'...");
-            video.clone().download(state).await?;
-        }
-        None => {
-            bail!("Could not extract embed URL from config 'video.embed_code' string (embed_url not captured)");
-        }
+        assert!(result.is_err());
+        assert!(state.notices().is_empty());
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn showcase_page_shell_is_fetched_again() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<script>dataForPlayer = {"clips":[{"config":"https://example.com/config"}]};</script>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let state = State::new(Config::default());
+        let clips = fetch_showcase_clips(&server.uri(), None, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            clips,
+            [ShowcaseClip::Config(
+                json!({ "config": "https://example.com/config" })
+            )]
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
 }
@@ -1,81 +1,214 @@
 use std::sync::Arc;
 
-use color_eyre::{eyre::Result, Report};
+use color_eyre::eyre::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use tracing::{debug, info, instrument, trace, Instrument};
+use scraper::{Html, Selector};
+use tracing::{debug, info, instrument, trace, warn};
 
 use crate::{
-    state::{video::Video, State},
+    state::{video::Video, Extraction, State},
     util,
 };
 
 static REGEX_TITLE_TAG: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"<title>(?P<title>.*?)</title>").unwrap());
 
+static SELECTOR_CANONICAL_LINK: Lazy<Selector> =
+    Lazy::new(|| Selector::parse(r#"link[rel="canonical"][href]"#).unwrap());
+
 #[instrument(skip(state))]
 pub(crate) async fn process_simple_player(
     player_url: &str,
     referer: Option<&str>,
     state: Arc<State>,
+    extraction: Extraction,
 ) -> Result<()> {
-    let video = Arc::new(Video::new(player_url, referer));
-    (*state).push_video(video.clone()).await;
-
-    tokio::try_join!(
-        async {
-            let video = video.clone();
-            let referer = referer.map(ToOwned::to_owned);
-            tokio::spawn(
-                async move {
-                    debug!("Fetch title for simple player '{}'...", video.url());
-                    extract_simple_player_title(video, referer.as_deref()).await?;
-                    Ok::<(), Report>(())
-                }
-                .in_current_span(),
-            )
-            .await?
-        },
-        async {
-            let video = video.clone();
-            tokio::spawn(
-                async move {
-                    let url = video.url();
-                    info!("Download simple player '{url}'...");
-                    video.clone().download(state).await?;
-
-                    Ok::<(), Report>(())
-                }
-                .in_current_span(),
-            )
-            .await?
-        }
-    )?;
+    let video = Arc::new(
+        Video::from_url(player_url)
+            .referer(referer)
+            .fetch_title(|video| Box::pin(extract_simple_player_title(video)))
+            .build(),
+    );
+    if !(*state).push_video(video.clone()).await {
+        return Ok(());
+    }
+    drop(extraction);
 
-    Ok(())
+    info!("Download simple player '{player_url}'...");
+    video.download(state).await
 }
 
 #[instrument]
-async fn extract_simple_player_title(video: Arc<Video>, referer: Option<&str>) -> Result<()> {
-    let response_text = util::fetch_with_retry(video.url(), referer, None)
-        .await?
-        .text()
-        .await?;
+async fn extract_simple_player_title(video: Arc<Video>) -> Result<()> {
+    let referer = video.referer();
+    let response_text = fetch_page(video.url(), referer).await?;
 
     trace!(%response_text, "Trying to extract the video title from '{}'...", video.url());
 
-    let maybe_captures = REGEX_TITLE_TAG.captures(&response_text);
+    let mut maybe_title = parse_title(&response_text);
+
+    // Crawlers are served a player page with an empty title, which links to the canonical page.
+    // Only then is the canonical page fetched as well.
+    if maybe_title.as_deref().is_some_and(is_crawler_player_title) {
+        if let Some(canonical_url) =
+            parse_canonical_url(&response_text).filter(|url| url != video.url())
+        {
+            debug!("Empty crawler player title, fetching canonical page '{canonical_url}'...");
+            // The player page was fetched fine, so an unavailable canonical page only leaves the video without title.
+            maybe_title = match fetch_page(&canonical_url, referer).await {
+                Ok(canonical_response_text) => {
+                    trace!(%canonical_response_text, "Trying to extract the video title from '{canonical_url}'...");
+                    parse_title(&canonical_response_text)
+                }
+                Err(e) => {
+                    warn!("Failed to fetch canonical page '{canonical_url}': {e:?}");
+                    None
+                }
+            };
+        }
+    }
 
-    if let Some(captures) = maybe_captures {
-        if let Some(title_match) = captures.name("title") {
-            let matched_title = htmlize::unescape(title_match.as_str());
+    match maybe_title {
+        Some(title) if !is_crawler_player_title(&title) => {
             info!(
-                "Matched title '{matched_title}' for simple player '{}'",
+                "Matched title '{title}' for simple player '{}'",
                 video.url()
             );
-            video.update_title(matched_title.into_owned()).await;
+            video.update_title(title).await;
         }
+        _ => debug!("No title found for simple player '{}'", video.url()),
     }
 
     Ok(())
 }
+
+async fn fetch_page(url: &str, referer: Option<&str>) -> Result<String> {
+    Ok(util::fetch_with_retry(url, referer, None)
+        .await?
+        .text()
+        .await?)
+}
+
+fn parse_title(html: &str) -> Option<String> {
+    let title_match = REGEX_TITLE_TAG.captures(html)?.name("title")?;
+
+    Some(htmlize::unescape(title_match.as_str()).into_owned())
+}
+
+// Character references in the URL, such as `&amp;`, are decoded by the HTML parser.
+fn parse_canonical_url(html: &str) -> Option<String> {
+    Html::parse_document(html)
+        .select(&SELECTOR_CANONICAL_LINK)
+        .find_map(|link| link.value().attr("href"))
+        .map(ToOwned::to_owned)
+}
+
+// The title of the crawler player page is empty, apart from the site name, e.g. `<title> on Vimeo</title>`.
+fn is_crawler_player_title(title: &str) -> bool {
+    title.trim().is_empty() || title.trim() == "on Vimeo"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use wiremock::{
+        matchers::{header, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::{is_crawler_player_title, parse_canonical_url, parse_title, process_simple_player};
+    use crate::{session::Config, state::State};
+
+    const CRAWLER_PLAYER_HTML: &str = r#"<!DOCTYPE html><html><head><title> on Vimeo</title><link rel="canonical" href="https://vimeo.com/123456789"></head></html>"#;
+
+    #[test]
+    fn detects_crawler_player_page() {
+        let title = parse_title(CRAWLER_PLAYER_HTML).unwrap();
+
+        assert!(is_crawler_player_title(&title));
+        assert_eq!(
+            parse_canonical_url(CRAWLER_PLAYER_HTML).as_deref(),
+            Some("https://vimeo.com/123456789")
+        );
+    }
+
+    #[test]
+    fn keeps_real_titles() {
+        let title =
+            parse_title("<html><head><title>My Video &amp; More on Vimeo</title></head></html>")
+                .unwrap();
+
+        assert_eq!(title, "My Video & More on Vimeo");
+        assert!(!is_crawler_player_title(&title));
+    }
+
+    #[tokio::test]
+    async fn title_is_fetched_alongside_download() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("Referer", "https://example.com/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("<html><head><title>My Video</title></head></html>"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let state = Arc::new(State::new(Config {
+            extract_only: true,
+            ..Config::default()
+        }));
+        let extraction = state.begin_extraction();
+
+        process_simple_player(
+            &server.uri(),
+            Some("https://example.com/"),
+            state.clone(),
+            extraction,
+        )
+        .await
+        .unwrap();
+
+        let video = state.video(1).await.unwrap();
+        assert_eq!(video.referer(), Some("https://example.com/"));
+        assert_eq!(video.title().await.as_deref(), Some("My Video"));
+    }
+
+    #[test]
+    fn canonical_link_attributes_may_be_reordered() {
+        assert_eq!(
+            parse_canonical_url(
+                "<html><head><link href='https://vimeo.com/123456789?a=1&amp;b=2' rel=canonical></head></html>"
+            )
+            .as_deref(),
+            Some("https://vimeo.com/123456789?a=1&b=2")
+        );
+    }
+
+    #[tokio::test]
+    async fn unavailable_canonical_page_leaves_video_without_title() {
+        let server = MockServer::start().await;
+        // Nothing listens on port 1, so the canonical page cannot be fetched.
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<html><head><title> on Vimeo</title><link rel="canonical" href="http://127.0.0.1:1/123456789"></head></html>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let state = Arc::new(State::new(Config {
+            extract_only: true,
+            ..Config::default()
+        }));
+        let extraction = state.begin_extraction();
+
+        process_simple_player(&server.uri(), None, state.clone(), extraction)
+            .await
+            .unwrap();
+
+        assert_eq!(state.video(1).await.unwrap().title().await.as_deref(), None);
+    }
+}
@@ -0,0 +1,50 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::time::MissedTickBehavior;
+use tracing::{info, instrument};
+
+use crate::state::{video::Stage, State};
+
+// Periodically log the progress of each running video, building a readable timeline in the log file
+// without OTLP export. Runs until aborted.
+#[instrument(skip(state))]
+pub(crate) async fn run(state: Arc<State>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // The first tick completes immediately, when there is no progress to log yet.
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        log_running_videos(&state).await;
+    }
+}
+
+async fn log_running_videos(state: &State) {
+    for video in state.videos().await.iter() {
+        let video_read = video.read().await;
+        if !matches!(video_read.stage(), Stage::Running { .. }) {
+            continue;
+        }
+
+        let cells = video_read
+            .progress_detail()
+            .and_then(|progress_detail| progress_detail.to_table_cells());
+        let [size, speed, eta, fragments] = cells.as_ref().map_or(["", "", "", ""], |cells| {
+            cells.each_ref().map(AsRef::as_ref)
+        });
+
+        info!(
+            url = video_read.url(),
+            title = video_read.title().map(String::as_str),
+            percent_done = video_read.percent_done(),
+            size,
+            speed,
+            eta,
+            fragments,
+            attempt = video_read.attempt(),
+            "Download progress"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde_json::{json, Value};
+use tracing::{info, instrument};
+
+use crate::state::{video::Stage as VideoStage, State};
+
+// Write a JSON summary of all videos to the given file path.
+#[instrument(skip(state))]
+pub(crate) async fn write_report(path: &Path, state: &State) -> Result<()> {
+    let report = serde_json::to_string_pretty(&summary(state).await)?;
+
+    info!("Writing report to '{}'...", path.display());
+    tokio::fs::write(path, report)
+        .await
+        .wrap_err_with(|| format!("Could not write report to '{}'", path.display()))?;
+
+    Ok(())
+}
+
+// Summarize each video's URL, title, final stage, output file and elapsed download time,
+// along with counts of succeeded, failed and skipped videos.
+pub(crate) async fn summary(state: &State) -> Value {
+    let mut succeeded = 0;
+    let mut failed = 0;
+    let mut skipped = 0;
+
+    let mut videos = Vec::new();
+    for video in &*state.videos().await {
+        let video = video.read().await;
+
+        match video.stage() {
+            VideoStage::Finished => succeeded += 1,
+            VideoStage::Failed | VideoStage::GeoBlocked { .. } => failed += 1,
+            // Never started, cancelled, skipped by the downloader, or interrupted by shutdown.
+            _ => skipped += 1,
+        }
+
+        videos.push(json!({
+            "url": video.url(),
+            "title": video.title(),
+            "stage": video.stage().name(),
+            "skip_reason": video.stage().skip_reason(),
+            "geo_block_reason": video.stage().geo_block_reason(),
+            "output_file": video.output_file(),
+            "simulated": video.is_simulated(),
+            "elapsed_secs": video.elapsed().map(|elapsed| elapsed.as_secs_f64()),
+        }));
+    }
+
+    json!({
+        "succeeded": succeeded,
+        "failed": failed,
+        "skipped": skipped,
+        "videos": videos,
+    })
+}
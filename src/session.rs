@@ -0,0 +1,417 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use color_eyre::eyre::{bail, Result};
+use futures::future;
+use reqwest::Url;
+use tokio::sync::broadcast;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    extract,
+    state::{Extraction, State},
+    util,
+};
+
+/// Download session configuration.
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)] // Independent options, mirroring command line flags.
+pub struct Config {
+    /// Path to the downloader, such as `yt-dlp` or `youtube-dl`.
+    pub downloader: String,
+    /// Options passed to the downloader.
+    pub downloader_options: Vec<String>,
+    /// Referer URL, for referer-restricted Vimeo showcases and players.
+    pub referer: Option<String>,
+    /// Derive the referer of a player URL from the player URL's own origin, unless `referer` is set.
+    pub referer_from_url: bool,
+    /// Refer to showcase clips by their showcase's URL, rather than by the embedding page or `referer`.
+    pub referer_from_showcase: bool,
+    /// Additional headers, as pairs of name and value, sent with all requests and passed to the downloader.
+    pub headers: Vec<(String, String)>,
+    /// Timeout of HTTP requests made while extracting videos.
+    pub http_timeout: Duration,
+    /// Password for password protected Vimeo showcases, events and videos.
+    pub video_password: Option<String>,
+    /// Maximum download rate per video, in the downloader's rate syntax, e.g. `50K` or `4.2M`.
+    pub limit_rate: Option<String>,
+    /// Maximum file size, in the downloader's size syntax, e.g. `50K` or `4.2G`. Larger videos are skipped.
+    pub max_filesize: Option<String>,
+    /// Output file name template, in the downloader's template syntax, e.g. `%(uploader)s/%(title)s.%(ext)s`.
+    pub output_template: Option<String>,
+    /// Restrict output file names to characters valid on Windows.
+    pub windows_filenames: bool,
+    /// Embed metadata, such as title, uploader and description, into each output file.
+    pub embed_metadata: bool,
+    /// Embed each video's thumbnail into its output file as cover art.
+    pub embed_thumbnail: bool,
+    /// Download subtitles in all available languages alongside each video.
+    pub subtitles: bool,
+    /// Also download automatically generated subtitles. Requires `subtitles`.
+    pub auto_subtitles: bool,
+    /// Pass `--simulate` to the downloader, which resolves each video without writing any file.
+    pub simulate: bool,
+    /// Maximum length of output file names, excluding the extension. Unlimited if `None`.
+    pub trim_filenames: Option<usize>,
+    /// Number of fragments of each fragmented download, such as HLS or DASH, fetched concurrently. The downloader's default if `None`.
+    pub concurrent_fragments: Option<u16>,
+    /// Resume partially downloaded files, e.g. left by a previous run, rather than restarting them.
+    pub continue_partial_downloads: bool,
+    /// Maximum number of concurrent downloads. Unlimited if `None`.
+    pub max_concurrent_downloads: Option<usize>,
+    /// Only embeds whose URL contains this string are downloaded from a page. All embeds if `None`.
+    pub url_contains: Option<String>,
+    /// Maximum number of unique videos to download, in order of discovery. Unlimited if `None`.
+    pub video_limit: Option<usize>,
+    /// Number of attempts at downloading each video, retrying failed downloads. At least 1.
+    pub download_attempts: u8,
+    /// Minimum time to sleep between starting downloads, to avoid being rate limited on large showcases. No sleep if `None`.
+    pub min_sleep_interval: Option<Duration>,
+    /// Maximum time to sleep between starting downloads, randomizing the sleep. Sleeps exactly `min_sleep_interval` if `None`.
+    pub max_sleep_interval: Option<Duration>,
+    /// Time to sleep between the downloader's own retries, in the downloader's syntax, e.g. `5` or `fragment:exp=1:20`.
+    pub retry_sleep: Option<String>,
+    /// Signal sent to running downloads on shutdown.
+    pub shutdown_signal: ShutdownSignal,
+    /// Minimum time between applying a video's progress lines, coalescing more frequent lines into the latest. Every line is applied if `None`.
+    pub line_throttle: Option<Duration>,
+    /// Only extract videos, without downloading them.
+    pub extract_only: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            downloader: String::from("yt-dlp"),
+            downloader_options: Vec::new(),
+            referer: None,
+            referer_from_url: false,
+            referer_from_showcase: false,
+            headers: Vec::new(),
+            http_timeout: Duration::from_secs(util::DEFAULT_HTTP_TIMEOUT_SECS),
+            video_password: None,
+            limit_rate: None,
+            max_filesize: None,
+            output_template: None,
+            windows_filenames: false,
+            embed_metadata: false,
+            embed_thumbnail: false,
+            subtitles: false,
+            auto_subtitles: false,
+            simulate: false,
+            trim_filenames: None,
+            concurrent_fragments: None,
+            continue_partial_downloads: true,
+            max_concurrent_downloads: None,
+            url_contains: None,
+            video_limit: None,
+            download_attempts: 1,
+            min_sleep_interval: None,
+            max_sleep_interval: None,
+            retry_sleep: None,
+            shutdown_signal: ShutdownSignal::default(),
+            line_throttle: None,
+            extract_only: false,
+        }
+    }
+}
+
+/// Signal sent to running downloads on shutdown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    /// Interrupt, letting the downloader mux partially downloaded streams into a video file
+    #[default]
+    Sigint,
+    /// Terminate, aborting faster without muxing partial downloads
+    Sigterm,
+}
+
+/// Download stage of a video, as reported by [`Event::VideoStage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VideoStage {
+    /// Discovered, but not yet downloading.
+    Initializing,
+    /// Waiting for a download slot, limited by [`Config::max_concurrent_downloads`].
+    Queued,
+    /// Downloading.
+    Running,
+    /// Downloading, but paused by the user.
+    Paused,
+    /// Interrupted, waiting for the downloader to exit.
+    ShuttingDown,
+    /// Downloaded.
+    Finished,
+    /// Failed to download, after all attempts.
+    Failed,
+    /// Cancelled before starting to download, e.g. on shutdown.
+    Cancelled,
+    /// Skipped by the downloader, e.g. as larger than [`Config::max_filesize`].
+    Skipped,
+    /// Not available in the downloader's region.
+    GeoBlocked,
+}
+
+impl VideoStage {
+    /// Machine-readable name, e.g. `geo_blocked`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            VideoStage::Initializing => "initializing",
+            VideoStage::Queued => "queued",
+            VideoStage::Running => "running",
+            VideoStage::Paused => "paused",
+            VideoStage::ShuttingDown => "shutting_down",
+            VideoStage::Finished => "finished",
+            VideoStage::Failed => "failed",
+            VideoStage::Cancelled => "cancelled",
+            VideoStage::Skipped => "skipped",
+            VideoStage::GeoBlocked => "geo_blocked",
+        }
+    }
+}
+
+impl fmt::Display for VideoStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Progress event, published while a session is downloading.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Event {
+    /// A video has been discovered.
+    VideoAdded {
+        /// The video's URL, identifying the video in subsequent events.
+        url: String,
+    },
+    /// A video's title has been determined.
+    VideoTitle {
+        /// The video's URL.
+        url: String,
+        /// The video's title.
+        title: String,
+    },
+    /// A video's download stage has changed.
+    VideoStage {
+        /// The video's URL.
+        url: String,
+        /// The new stage.
+        stage: VideoStage,
+    },
+    /// The downloader has printed a line of output for a video.
+    VideoLine {
+        /// The video's URL.
+        url: String,
+        /// The downloader's output line.
+        line: String,
+    },
+    /// All videos have been processed.
+    Done,
+}
+
+/// Download session, extracting and downloading all videos from a URL.
+///
+/// ```no_run
+/// # async fn example() -> color_eyre::eyre::Result<()> {
+/// let session = showcase_dl::Session::new(showcase_dl::Config::default())?;
+///
+/// let mut events = session.subscribe();
+/// tokio::spawn(async move {
+///     while let Ok(event) = events.recv().await {
+///         println!("{event:?}");
+///     }
+/// });
+///
+/// session.download("https://vimeo.com/showcase/123").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Session {
+    state: Arc<State>,
+    referer: Option<String>,
+    referer_from_url: bool,
+}
+
+impl Session {
+    /// Create a new download session.
+    ///
+    /// The HTTP client is shared by all sessions, using the timeout and headers of the latest session.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of [`Config::headers`] is not a valid HTTP header.
+    pub fn new(config: Config) -> Result<Self> {
+        util::init_client(config.http_timeout, &config.headers)?;
+
+        let referer = config.referer.clone();
+        let referer_from_url = config.referer_from_url;
+
+        Ok(Self {
+            state: Arc::new(State::new(config)),
+            referer,
+            referer_from_url,
+        })
+    }
+
+    /// Subscribe to progress events.
+    ///
+    /// Events are buffered for slow subscribers up to a limit.
+    /// Beyond it, the oldest events are dropped and reported as [`broadcast::error::RecvError::Lagged`].
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.state.subscribe()
+    }
+
+    /// Extract all videos from the given URL and download them, returning once all downloads have ended.
+    ///
+    /// The URL may either point to a page containing embedded videos,
+    /// or to a Vimeo showcase, event, album, simple player or playlist.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the URL is invalid, or if extracting videos fails.
+    /// Failed downloads of individual videos are reported as [`Event::VideoStage`] instead.
+    #[instrument(skip(self))]
+    pub async fn download(&self, url: &str) -> Result<()> {
+        self.download_all([url]).await
+    }
+
+    /// Extract all videos from each of the given URLs and download them, returning once all downloads have ended.
+    ///
+    /// See [`Session::download`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if any URL is invalid, or if extracting videos fails.
+    #[instrument(skip(self, urls))]
+    pub async fn download_all<'a>(&self, urls: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        self.download_batch(urls.into_iter().map(|url| (url, None)))
+            .await
+    }
+
+    /// Extract all videos from each of the given URLs and download them, returning once all downloads have ended.
+    ///
+    /// Each URL is paired with its own referer, overriding [`Config::referer`] if given.
+    /// See [`Session::download`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if any URL is invalid, or if extracting videos fails.
+    /// A failing entry does not cancel the others: The error is returned once all entries have ended.
+    #[instrument(skip(self, entries))]
+    pub async fn download_batch<'a>(
+        &self,
+        entries: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+    ) -> Result<()> {
+        let extraction = self.state.begin_extraction();
+        let results = future::join_all(extraction.branch(entries).into_iter().map(
+            |((url, referer), extraction)| async move {
+                (
+                    url,
+                    self.extract_and_download(url, referer, extraction).await,
+                )
+            },
+        ))
+        .await;
+        let entry_count = results.len();
+
+        let mut errors = Vec::new();
+        for (url, result) in results {
+            if let Err(e) = result {
+                warn!("Failed to process '{url}': {e:?}");
+                self.state
+                    .push_notice(format!("Failed to process '{url}': {e}"));
+                errors.push(e);
+            }
+        }
+
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.remove(0)),
+            error_count => bail!("{error_count} of {entry_count} URLs failed to process"),
+        }
+
+        self.state.set_stage_done().await;
+
+        Ok(())
+    }
+
+    async fn extract_and_download(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+        extraction: Extraction,
+    ) -> Result<()> {
+        let url = Url::parse(url)?;
+        debug!("Parsed page URL: {url:#?}");
+
+        if extract::player::is_player_url(&url) {
+            let referer = extract::embeds::player_referer(
+                &url,
+                referer.or(self.referer.as_deref()),
+                self.referer_from_url,
+            );
+            extract::player::download_from_player(
+                url,
+                referer.as_deref(),
+                self.state.clone(),
+                extraction,
+            )
+            .await?;
+        } else {
+            extract::embeds::extract_and_download_embeds(url, self.state.clone(), extraction)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn state(&self) -> &Arc<State> {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Config, Session};
+    use crate::state::{
+        video::{downloader::ScriptedDownloader, Stage},
+        State,
+    };
+
+    #[tokio::test]
+    async fn failing_batch_entry_does_not_cancel_the_others() {
+        let session = Session {
+            state: Arc::new(State::new(Config::default()).with_downloader_command(
+                ScriptedDownloader {
+                    lines: vec![
+                        r#"{"url": "https://www.youtube.com/watch?v=a"}"#,
+                        r#"{"url": "https://www.youtube.com/watch?v=b"}"#,
+                    ],
+                    exit_code: Some(0),
+                },
+            )),
+            referer: None,
+            referer_from_url: false,
+        };
+
+        assert!(session
+            .download_batch([
+                ("not a URL", None),
+                ("https://www.youtube.com/playlist?list=PL1", None),
+            ])
+            .await
+            .is_err());
+
+        let videos = session.state.videos().await;
+        assert_eq!(videos.len(), 2);
+        for video in videos.iter() {
+            assert!(matches!(video.read().await.stage(), Stage::Finished));
+        }
+        assert!(session.state.notices()[0].contains("'not a URL'"));
+    }
+}
@@ -1,22 +1,133 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
 
 use color_eyre::eyre::{eyre, Result};
 use futures::future::join_all;
-use tokio::sync::{oneshot, RwLock, RwLockReadGuard};
-use tracing::{debug, info, instrument};
+use tokio::sync::{
+    broadcast, oneshot, watch, Mutex as AsyncMutex, Notify, RwLock, RwLockReadGuard, Semaphore,
+    SemaphorePermit,
+};
+use tracing::{debug, info, instrument, warn};
 
-use self::video::Video;
+use self::video::{
+    downloader::{DownloaderCommand, SystemDownloader},
+    Stage as VideoStage, Video,
+};
+use crate::session::{Config, Event, ShutdownSignal};
 
 pub(crate) mod video;
 
+// Number of events buffered for lagging subscribers.
+const EVENT_CAPACITY: usize = 1024;
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)] // Mirrors the independent flags of `Config`.
 pub(crate) struct State {
     pub(crate) downloader: String,
+    // Creates the downloader command, replaced by a fake downloader in tests.
+    downloader_command: Arc<dyn DownloaderCommand>,
     pub(crate) downloader_options: Vec<String>,
+    pub(crate) video_password: Option<String>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) limit_rate: Option<String>,
+    pub(crate) max_filesize: Option<String>,
+    pub(crate) output_template: Option<String>,
+    pub(crate) windows_filenames: bool,
+    pub(crate) embed_metadata: bool,
+    pub(crate) embed_thumbnail: bool,
+    pub(crate) subtitles: bool,
+    pub(crate) auto_subtitles: bool,
+    pub(crate) simulate: bool,
+    pub(crate) trim_filenames: Option<usize>,
+    pub(crate) concurrent_fragments: Option<u16>,
+    pub(crate) continue_partial_downloads: bool,
+    pub(crate) download_attempts: u8,
+    pub(crate) retry_sleep: Option<String>,
+    pub(crate) shutdown_signal: ShutdownSignal,
+    pub(crate) line_throttle: Option<Duration>,
+    pub(crate) referer_from_showcase: bool,
+    pub(crate) extract_only: bool,
+
+    // Restricts the embeds extracted from a page, if set.
+    url_contains: Option<String>,
+
+    // Limits the number of unique videos, if set.
+    video_limit: Option<usize>,
+
+    // Limits the number of concurrent downloads, if set.
+    pub(crate) max_concurrent_downloads: Option<usize>,
+    download_permits: Option<Semaphore>,
+
+    // Minimum and maximum time between starting downloads, if set.
+    sleep_interval: Option<(Duration, Duration)>,
+
+    // Point in time the latest download started. Locked while sleeping, to space out download starts one by one.
+    last_download_start: AsyncMutex<Option<Instant>>,
+
+    // Notified once shutdown is initiated, interrupting sleeps between download starts.
+    shutdown: Notify,
 
     stage: RwLock<Stage>,
     videos: RwLock<Vec<Arc<Video>>>,
+
+    // Point in time the run started, and - once all processing is done - ended.
+    started_at: Instant,
+    done_at: Mutex<Option<Instant>>,
+
+    // Point in time of the last downloader output line, used to slow down rendering while idle.
+    last_activity: Mutex<Instant>,
+
+    // Set whenever the displayed application state changes, to redraw the UI only when necessary.
+    // Video changes are published as events instead.
+    changed: AtomicBool,
+
+    // Whether all downloads have been paused by the user.
+    paused: AtomicBool,
+
+    // Messages about noteworthy outcomes which are not tied to any video, such as empty showcases.
+    notices: Mutex<Vec<String>>,
+
+    // Output files of in-flight downloads, mapped to the URL of the video claiming each.
+    output_files: Mutex<HashMap<String, String>>,
+
+    // Publishes progress events to subscribers, such as library consumers.
+    events: broadcast::Sender<Event>,
+
+    // Set once the latest extraction has ended, i.e. all its videos have been pushed.
+    extracted: Arc<watch::Sender<bool>>,
+}
+
+// Held by each branch of an extraction which may still push videos, and handed on when branching out.
+// The extraction ends once the last branch is dropped, e.g. right before downloading its pushed video.
+#[derive(Debug, Clone)]
+pub(crate) struct Extraction {
+    _end: Arc<ExtractionEnd>,
 }
 
+impl Extraction {
+    // Hand each item its own branch of this extraction, ending this branch.
+    pub(crate) fn branch<T>(self, items: impl IntoIterator<Item = T>) -> Vec<(T, Extraction)> {
+        items.into_iter().map(|item| (item, self.clone())).collect()
+    }
+}
+
+#[derive(Debug)]
+struct ExtractionEnd(Arc<watch::Sender<bool>>);
+
+impl Drop for ExtractionEnd {
+    fn drop(&mut self) {
+        self.0.send_replace(true);
+    }
+}
+
+#[derive(Debug)]
 pub(crate) enum Stage {
     Initializing,
     FetchingSource(String),
@@ -27,42 +138,322 @@ pub(crate) enum Stage {
 }
 
 impl State {
-    pub(crate) fn new(downloader: String, downloader_options: Vec<String>) -> Self {
+    pub(crate) fn new(config: Config) -> Self {
+        let Config {
+            downloader,
+            downloader_options,
+            video_password,
+            headers,
+            limit_rate,
+            max_filesize,
+            output_template,
+            windows_filenames,
+            embed_metadata,
+            embed_thumbnail,
+            subtitles,
+            auto_subtitles,
+            simulate,
+            trim_filenames,
+            concurrent_fragments,
+            continue_partial_downloads,
+            max_concurrent_downloads,
+            download_attempts,
+            retry_sleep,
+            shutdown_signal,
+            line_throttle,
+            referer_from_showcase,
+            min_sleep_interval,
+            max_sleep_interval,
+            extract_only,
+            url_contains,
+            video_limit,
+            ..
+        } = config;
+
         Self {
             downloader,
+            downloader_command: Arc::new(SystemDownloader),
             downloader_options,
+            video_password,
+            headers,
+            limit_rate,
+            max_filesize,
+            output_template,
+            windows_filenames,
+            embed_metadata,
+            embed_thumbnail,
+            subtitles,
+            auto_subtitles,
+            simulate,
+            trim_filenames,
+            concurrent_fragments,
+            continue_partial_downloads,
+            // Attempt each download at least once.
+            download_attempts: download_attempts.max(1),
+            retry_sleep,
+            shutdown_signal,
+            line_throttle,
+            referer_from_showcase,
+            extract_only,
+
+            url_contains,
+
+            video_limit,
+
+            max_concurrent_downloads,
+            download_permits: max_concurrent_downloads.map(Semaphore::new),
+
+            // A maximum below the minimum is raised to it.
+            sleep_interval: min_sleep_interval
+                .map(|min| (min, max_sleep_interval.map_or(min, |max| max.max(min)))),
+            last_download_start: AsyncMutex::new(None),
+            shutdown: Notify::new(),
 
             stage: RwLock::new(Stage::Initializing),
             videos: RwLock::new(vec![]),
+
+            started_at: Instant::now(),
+            done_at: Mutex::new(None),
+
+            last_activity: Mutex::new(Instant::now()),
+
+            changed: AtomicBool::new(true),
+
+            paused: AtomicBool::new(false),
+
+            notices: Mutex::new(vec![]),
+
+            output_files: Mutex::new(HashMap::new()),
+
+            events: broadcast::channel(EVENT_CAPACITY).0,
+
+            extracted: Arc::new(watch::Sender::new(false)),
         }
     }
 
+    pub(crate) fn begin_extraction(&self) -> Extraction {
+        self.extracted.send_replace(false);
+        Extraction {
+            _end: Arc::new(ExtractionEnd(self.extracted.clone())),
+        }
+    }
+
+    // Wait until the latest extraction has ended, while its videos may still be downloading.
+    pub(crate) async fn extracted(&self) {
+        // The sender lives as long as the state, so that waiting can not fail.
+        self.extracted
+            .subscribe()
+            .wait_for(|extracted| *extracted)
+            .await
+            .ok();
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_downloader_command(
+        mut self,
+        downloader_command: impl DownloaderCommand + 'static,
+    ) -> Self {
+        self.downloader_command = Arc::new(downloader_command);
+        self
+    }
+
+    // A command running the configured downloader, to which arguments are yet to be added.
+    pub(crate) fn downloader_command(&self) -> tokio::process::Command {
+        self.downloader_command.command(&self.downloader)
+    }
+
+    // A downloader command sending the given referer, the additional headers and the video password,
+    // as do all requests made while extracting videos.
+    pub(crate) fn downloader_request_command(
+        &self,
+        referer: Option<&str>,
+    ) -> tokio::process::Command {
+        let mut command = self.downloader_command();
+
+        if let Some(referer) = referer {
+            command
+                .arg("--add-header")
+                .arg(format!("Referer:{referer}"));
+        }
+
+        for (name, value) in &self.headers {
+            command.arg("--add-header").arg(format!("{name}:{value}"));
+        }
+
+        if let Some(ref video_password) = self.video_password {
+            command.arg("--video-password").arg(video_password);
+        }
+
+        command
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: Event) {
+        // Sending only fails if nobody is subscribed, which is fine.
+        self.events.send(event).ok();
+    }
+
+    // Whether an embed URL discovered on a page is to be downloaded.
+    pub(crate) fn is_embed_wanted(&self, embed_url: &str) -> bool {
+        self.url_contains
+            .as_ref()
+            .is_none_or(|url_contains| embed_url.contains(url_contains.as_str()))
+    }
+
+    pub(crate) fn mark_changed(&self) {
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    // Whether the application state has changed since the last call.
+    pub(crate) fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+
+    // Whether any spinner is animated, which requires redrawing on every tick.
+    pub(crate) async fn is_animated(&self) -> bool {
+        if matches!(
+            *self.stage.read().await,
+            Stage::Initializing | Stage::FetchingSource(_)
+        ) {
+            return true;
+        }
+
+        for video in &*self.videos().await {
+            if matches!(*video.stage().await, VideoStage::Initializing) {
+                return true;
+            }
+        }
+
+        false
+    }
+
     #[instrument(skip(self))]
     pub(crate) async fn set_stage_fetching_source(&self, page_url: impl Into<String> + Debug) {
         *self.stage.write().await = Stage::FetchingSource(page_url.into());
+        self.mark_changed();
     }
 
     #[instrument(skip(self))]
     pub(crate) async fn set_stage_processing(&self) {
         *self.stage.write().await = Stage::Processing;
+        self.mark_changed();
     }
 
     #[instrument(skip(self))]
     pub(crate) async fn set_stage_done(&self) {
         *self.stage.write().await = Stage::Done;
+        *self.done_at.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+        self.mark_changed();
+        self.publish(Event::Done);
     }
 
-    pub(crate) async fn stage(&self) -> RwLockReadGuard<Stage> {
+    pub(crate) async fn stage(&self) -> RwLockReadGuard<'_, Stage> {
         self.stage.read().await
     }
 
+    // Add a video, unless the video limit has been reached. Returns whether the video was added.
+    // Repeated URLs do not count towards the limit.
     #[instrument(skip(self))]
-    pub(crate) async fn push_video(&self, video: Arc<Video>) {
+    pub(crate) async fn push_video(&self, video: Arc<Video>) -> bool {
         let mut videos = self.videos.write().await;
+
+        if let Some(video_limit) = self.video_limit {
+            if Self::unique_video_count(&videos) >= video_limit
+                && !videos.iter().any(|pushed| pushed.url() == video.url())
+            {
+                debug!(
+                    "Video limit of {video_limit} reached, skipping '{}'.",
+                    video.url()
+                );
+                return false;
+            }
+        }
+
+        video.attach_events(self.events.clone());
+        self.publish(Event::VideoAdded {
+            url: video.url().to_owned(),
+        });
+
+        video.set_index(videos.len() + 1);
         (*videos).push(video);
+        self.mark_changed();
+
+        true
     }
 
-    pub(crate) async fn videos(&self) -> RwLockReadGuard<Vec<Arc<Video>>> {
+    // Number of further unique videos which may be added, used to cut extraction short.
+    pub(crate) async fn remaining_video_slots(&self) -> usize {
+        match self.video_limit {
+            Some(video_limit) => {
+                video_limit.saturating_sub(Self::unique_video_count(&self.videos().await))
+            }
+            None => usize::MAX,
+        }
+    }
+
+    fn unique_video_count(videos: &[Arc<Video>]) -> usize {
+        videos
+            .iter()
+            .map(|video| video.url())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    // Wait for a download slot. Without a concurrency limit, no permit is required.
+    pub(crate) fn limits_concurrent_downloads(&self) -> bool {
+        self.download_permits.is_some()
+    }
+
+    pub(crate) async fn acquire_download_permit(&self) -> Result<Option<SemaphorePermit<'_>>> {
+        match self.download_permits {
+            Some(ref download_permits) => Ok(Some(download_permits.acquire().await?)),
+            None => Ok(None),
+        }
+    }
+
+    // Sleep a random interval since the latest download start, if configured, to avoid being rate limited.
+    // Returns early once shutdown is initiated.
+    pub(crate) async fn pace_download_start(&self) {
+        let Some((min, max)) = self.sleep_interval else {
+            return;
+        };
+
+        let mut last_download_start = self.last_download_start.lock().await;
+
+        if let Some(last_download_start) = *last_download_start {
+            let sleep_interval = min + max.saturating_sub(min).mul_f64(fastrand::f64());
+            debug!("Sleeping {sleep_interval:?} before starting the next download.");
+
+            // Register for the notification before checking the stage, so that no shutdown is missed.
+            let shutdown = self.shutdown.notified();
+            tokio::pin!(shutdown);
+            shutdown.as_mut().enable();
+            if self.is_shutting_down().await {
+                return;
+            }
+
+            tokio::select! {
+                () = tokio::time::sleep_until((last_download_start + sleep_interval).into()) => {},
+                () = shutdown => return,
+            }
+        }
+
+        *last_download_start = Some(Instant::now());
+    }
+
+    // Look up a video by its index, counting from 1 in order of insertion.
+    pub(crate) async fn video(&self, index: usize) -> Option<Arc<Video>> {
+        let videos = self.videos().await;
+        index
+            .checked_sub(1)
+            .and_then(|position| videos.get(position))
+            .cloned()
+    }
+
+    pub(crate) async fn videos(&self) -> RwLockReadGuard<'_, Vec<Arc<Video>>> {
         self.videos.read().await
     }
 
@@ -75,16 +466,18 @@ impl State {
 
         // Set flag to refuse accepting new downloads (spawning new children).
         *self.stage.write().await = Stage::ShuttingDown;
+        self.mark_changed();
+        self.shutdown.notify_waiters();
 
         let mut children_shutdown = Vec::new();
 
-        // Send SIGINT to all existing children.
+        // Send the shutdown signal - SIGINT by default - to all existing children.
         //
-        // This causes the downloader to initiate clean shutdown
+        // SIGINT causes the downloader to initiate clean shutdown
         // by muxing partially downloaded video and audio streams.
         let videos = self.videos().await;
 
-        debug!("Sending SIGINT to child processes.");
+        debug!("Sending {:?} to child processes.", self.shutdown_signal);
         for video in &(*videos) {
             // Take each running download's single-use shutdown signal.
             //
@@ -94,7 +487,8 @@ impl State {
                 children_shutdown.push(shutdown_signal);
             }
 
-            (*video).initiate_shutdown().await?;
+            // Queued downloads are cancelled, so that they never start, while running ones are interrupted.
+            (*video).cancel(self.shutdown_signal).await?;
         }
         drop(videos);
 
@@ -113,7 +507,211 @@ impl State {
         Ok(())
     }
 
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    // Pause all running downloads, or resume all paused downloads.
+    #[instrument(skip(self))]
+    pub(crate) async fn toggle_pause(&self) {
+        let pause = !self.paused.fetch_xor(true, Ordering::Relaxed);
+        self.mark_changed();
+
+        info!(
+            "{} all downloads.",
+            if pause { "Pausing" } else { "Resuming" }
+        );
+        for video in &*self.videos().await {
+            let result = if pause {
+                video.pause().await
+            } else {
+                video.resume().await
+            };
+
+            // The child process may have exited in the meantime.
+            if let Err(e) = result {
+                warn!(
+                    "Could not {} '{}': {e}",
+                    if pause { "pause" } else { "resume" },
+                    video.url()
+                );
+            }
+        }
+    }
+
+    // All processing is done and every video has either finished, failed, been cancelled, skipped or geo-blocked.
+    pub(crate) async fn is_done(&self) -> bool {
+        if !matches!(*self.stage.read().await, Stage::Done) {
+            return false;
+        }
+
+        for video in &*self.videos().await {
+            if !matches!(
+                *video.stage().await,
+                VideoStage::Finished
+                    | VideoStage::Failed
+                    | VideoStage::Cancelled
+                    | VideoStage::Skipped { .. }
+                    | VideoStage::GeoBlocked { .. }
+            ) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Count videos which have finished and failed, respectively.
+    pub(crate) async fn count_finished_and_failed(&self) -> (usize, usize) {
+        let mut finished = 0;
+        let mut failed = 0;
+
+        for video in &*self.videos().await {
+            match *video.stage().await {
+                VideoStage::Finished => finished += 1,
+                VideoStage::Failed | VideoStage::GeoBlocked { .. } => failed += 1,
+                _ => {}
+            }
+        }
+
+        (finished, failed)
+    }
+
+    pub(crate) fn push_notice(&self, notice: String) {
+        self.notices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(notice);
+        self.mark_changed();
+    }
+
+    pub(crate) fn notices(&self) -> Vec<String> {
+        self.notices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    // Claim an output file for the video of the given URL.
+    // Returns the URL of another in-flight video, if that video has already claimed the same output file.
+    pub(crate) fn claim_output_file(&self, output_file: &str, video_url: &str) -> Option<String> {
+        match self
+            .output_files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(output_file.to_owned())
+        {
+            Entry::Occupied(claimed) if claimed.get() != video_url => Some(claimed.get().clone()),
+            Entry::Occupied(_) => None,
+            Entry::Vacant(unclaimed) => {
+                unclaimed.insert(video_url.to_owned());
+                None
+            }
+        }
+    }
+
+    // Release all output files claimed by the video of the given URL, once its download has ended.
+    pub(crate) fn release_output_files(&self, video_url: &str) {
+        self.output_files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|_, claimed_by| claimed_by != video_url);
+    }
+
+    pub(crate) fn record_activity(&self) {
+        *self
+            .last_activity
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Instant::now();
+    }
+
+    // Wall-clock time since the run started, stopping once all processing is done.
+    pub(crate) fn run_duration(&self) -> Duration {
+        self.done_at
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started_at)
+    }
+
+    pub(crate) fn idle_duration(&self) -> Duration {
+        self.last_activity
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .elapsed()
+    }
+
     pub(crate) async fn is_shutting_down(&self) -> bool {
         matches!(*self.stage.read().await, Stage::ShuttingDown)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::sync::oneshot;
+
+    use super::{State, Video};
+    use crate::session::Config;
+
+    #[tokio::test]
+    async fn shutdown_interrupts_sleep_between_download_starts() {
+        let state = Arc::new(State::new(Config {
+            min_sleep_interval: Some(Duration::from_secs(30)),
+            ..Config::default()
+        }));
+
+        // The first download starts right away.
+        tokio::time::timeout(Duration::from_secs(1), state.pace_download_start())
+            .await
+            .unwrap();
+
+        let paced = tokio::spawn({
+            let state = state.clone();
+            async move { state.pace_download_start().await }
+        });
+        tokio::task::yield_now().await;
+
+        let (tx_shutdown_complete, _rx_shutdown_complete) = oneshot::channel();
+        state.initiate_shutdown(tx_shutdown_complete).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(1), paced)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn output_file_collisions_are_detected_until_released() {
+        let state = State::new(Config::default());
+
+        assert_eq!(state.claim_output_file("Title.mp4", "https://a"), None);
+        // Repeated claims by the same video are no collision.
+        assert_eq!(state.claim_output_file("Title.mp4", "https://a"), None);
+        assert_eq!(
+            state.claim_output_file("Title.mp4", "https://b").as_deref(),
+            Some("https://a")
+        );
+
+        state.release_output_files("https://a");
+        assert_eq!(state.claim_output_file("Title.mp4", "https://b"), None);
+    }
+
+    #[tokio::test]
+    async fn video_limit_counts_unique_urls() {
+        let state = State::new(Config {
+            video_limit: Some(2),
+            ..Config::default()
+        });
+        let video = |url: &str| Arc::new(Video::new(url, None::<String>));
+
+        assert!(state.push_video(video("https://a")).await);
+        assert!(state.push_video(video("https://a")).await);
+        assert_eq!(state.remaining_video_slots().await, 1);
+        assert!(state.push_video(video("https://b")).await);
+        assert!(!state.push_video(video("https://c")).await);
+        assert_eq!(state.remaining_video_slots().await, 0);
+        assert_eq!(state.videos().await.len(), 3);
+    }
+}
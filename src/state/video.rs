@@ -1,9 +1,16 @@
-use std::{fmt::Debug, num::NonZeroU32, process::Stdio, sync::Arc};
+use std::{
+    fmt::Debug,
+    num::NonZeroU32,
+    process::Stdio,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::{Duration, Instant},
+};
 
 use color_eyre::{
     eyre::{eyre, Result, WrapErr},
     Report,
 };
+use futures::future::BoxFuture;
 use nix::sys::signal::{self, Signal};
 use nix::unistd::Pid;
 use once_cell::sync::Lazy;
@@ -11,49 +18,226 @@ use regex::Regex;
 use tokio::{
     io::{AsyncBufReadExt, AsyncRead, BufReader},
     process::{Child, Command},
-    sync::{oneshot, RwLock, RwLockReadGuard},
+    sync::{broadcast, oneshot, Notify, RwLock, RwLockReadGuard},
     task::JoinHandle,
 };
 use tracing::{debug, error, info, instrument, trace, warn, Instrument};
 
-use crate::util::maybe_join;
+use crate::{
+    metrics,
+    session::{Event, ShutdownSignal, VideoStage},
+    util::maybe_join,
+};
 use progress::ProgressDetail;
 
 use super::State;
 
+pub(crate) mod downloader;
 pub(crate) mod progress;
 
-// TODO: Consider wrapping the entire Video in an RwLock or Mutex, rather than the individual fields.
 #[derive(Debug)]
 pub(crate) struct Video {
-    stage: RwLock<Stage>,
+    // Position in order of insertion into the application state, counting from 1.
+    index: OnceLock<usize>,
+    url: String,
+    referer: Option<String>,
+    // Preview image URL, if known from the video's source, such as a showcase clip config.
+    thumbnail_url: Option<String>,
+    // Fetches the title from the video's page alongside the download, if set.
+    fetch_title: Option<TitleFetcher>,
+
+    // All mutable fields behind a single lock, so that rendering a video takes a single read,
+    // and applying downloader output lines a single write.
+    fields: RwLock<VideoState>,
+
+    // Progress lines coalesced with `--throttle-ui`.
+    // Kept apart from `fields`, as holding back a line must not take the write lock contended by rendering.
+    line_throttle: Mutex<LineThrottle>,
+
+    // Notified once the user cancels the download while it is queued.
+    cancellation: Notify,
+
+    // Publishes progress events, once the video has been pushed to the application state.
+    // Percent done and output file are derived from lines, and elapsed time is set before the final stage,
+    // so line and stage events cover every displayed field.
+    events: OnceLock<broadcast::Sender<Event>>,
+}
+
+#[derive(Debug)]
+struct VideoState {
+    stage: Stage,
+    title: Option<String>,
+    line: Option<String>,
+    output_file: Option<String>,
+    percent_done: Option<f64>,
+    elapsed: Option<Duration>,
+
+    // Point in time of the last change of `percent_done`, or of the download (re-)starting.
+    last_progress_at: Instant,
+
+    // Number of the current download attempt, counting from 1.
+    attempt: u8,
+
+    // The downloader's own latest retry, e.g. of a fragment, as `retry/retries`, until progress resumes.
+    // Unlike `attempt`, these retries happen within a single downloader process.
+    downloader_retry: Option<String>,
+
+    // Total size reported on completion, retained as later lines, such as merger output, lack it.
+    final_size: Option<String>,
+
+    // Command line of the latest spawned downloader process, with secrets redacted.
+    command: Option<String>,
+
+    // Byte offset at which the downloader resumed a partially downloaded file, e.g. left by a previous run.
+    resumed_at_byte: Option<u64>,
+
+    // Subtitle files written alongside the video.
+    subtitle_files: Vec<String>,
+
+    // Reason given by the downloader for intentionally not downloading the video, e.g. exceeding `--max-filesize`.
+    skip_reason: Option<String>,
+
+    // Geo-restriction message of the downloader, e.g. "This video is not available in your country".
+    geo_block_reason: Option<String>,
+
+    // Latest `ERROR:` line of the current attempt, reported only if the downloader exits with an error,
+    // as the downloader may still succeed, e.g. with a fallback format.
+    downloader_error: Option<String>,
+
+    // Set once the downloader is spawned with `--simulate`, which resolves the video without writing any file.
+    simulated: bool,
+
+    // Set while the downloader merges separately downloaded formats into the output file,
+    // which takes a while after all formats have been downloaded to 100 %.
+    merging: bool,
+
+    // Set while the downloader reports progress of a subtitle file, rather than of the video itself.
+    receiving_subtitles: bool,
+}
+
+// Severity of a downloader output line, by its prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+// Bundles a video's URL with its optional referer and title, see `Video::from_url`.
+#[derive(Debug)]
+#[must_use]
+pub(crate) struct VideoBuilder {
     url: String,
     referer: Option<String>,
-    title: RwLock<Option<String>>,
-    line: RwLock<Option<String>>,
-    output_file: RwLock<Option<String>>,
-    percent_done: RwLock<Option<f64>>,
+    title: Option<String>,
+    fetch_title: Option<TitleFetcher>,
+}
+
+// Fetches a video's title, updating the video if a title was found.
+pub(crate) type TitleFetcher = fn(Arc<Video>) -> BoxFuture<'static, Result<()>>;
+
+impl VideoBuilder {
+    pub(crate) fn referer(mut self, referer: Option<impl Into<String>>) -> Self {
+        self.referer = referer.map(Into::into);
+        self
+    }
+
+    // Shown until replaced by the fetched title, if the title is fetched, too.
+    pub(crate) fn title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    // Fetch the title alongside the download, rather than separately.
+    pub(crate) fn fetch_title(mut self, fetch_title: TitleFetcher) -> Self {
+        self.fetch_title = Some(fetch_title);
+        self
+    }
+
+    pub(crate) fn build(self) -> Video {
+        Video {
+            fetch_title: self.fetch_title,
+            ..Video::new_with_title(self.url, self.referer, self.title)
+        }
+    }
+}
+
+// A downloader output line, with all of its changes to the video parsed.
+struct ParsedLine {
+    line: String,
+    is_merging: bool,
+    subtitle_file: Option<String>,
+    output_file: Option<String>,
+    percent_done: Option<f64>,
+    final_size: Option<String>,
+    downloader_retry: Option<String>,
+    resumed_at_byte: Option<u64>,
+    skip_reason: Option<String>,
+    geo_block_reason: Option<String>,
+    is_error: bool,
+}
+
+impl ParsedLine {
+    fn parse(line: String) -> Self {
+        Self {
+            is_merging: RE_OUTPUT_FILE_MERGING.is_match(&line),
+            subtitle_file: parse_subtitle_file(&line).map(ToOwned::to_owned),
+            output_file: parse_output_file(&line).map(ToOwned::to_owned),
+            percent_done: parse_percent_done(&line),
+            final_size: ProgressDetail::parse(&line, None)
+                .completed_size()
+                .map(ToOwned::to_owned),
+            downloader_retry: parse_downloader_retry(&line),
+            resumed_at_byte: parse_resumed_at_byte(&line),
+            skip_reason: parse_skip_reason(&line).map(ToOwned::to_owned),
+            geo_block_reason: parse_geo_block_reason(&line).map(ToOwned::to_owned),
+            is_error: line_severity(&line) == LineSeverity::Error,
+            line,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LineThrottle {
+    // Point in time the latest progress line was applied.
+    applied_at: Option<Instant>,
+    // Latest progress line held back since, applied before any later line, or once the output ends.
+    pending: Option<String>,
 }
 
 #[derive(Debug)]
 pub(crate) enum Stage {
     Initializing,
+    Queued,
     Running {
         process_id: u32,
         shutdown_signal: Option<oneshot::Receiver<()>>,
     },
+    // The child process is stopped, but kept alive to be resumed.
+    Paused {
+        process_id: u32,
+        shutdown_signal: Option<oneshot::Receiver<()>>,
+    },
     ShuttingDown,
     Finished,
     Failed,
+    // Cancelled by the user while queued, before ever starting.
+    Cancelled,
+    // Intentionally not downloaded by the downloader, e.g. exceeding `--max-filesize`.
+    Skipped {
+        reason: String,
+    },
+    // Refused by the video's host for the current location, e.g. downloadable via a proxy.
+    GeoBlocked {
+        reason: String,
+    },
 }
 
 pub(crate) struct VideoRead<'a> {
-    stage: RwLockReadGuard<'a, Stage>,
+    index: Option<usize>,
     url: &'a str,
-    title: RwLockReadGuard<'a, Option<String>>,
-    line: RwLockReadGuard<'a, Option<String>>,
-    output_file: RwLockReadGuard<'a, Option<String>>,
-    percent_done: RwLockReadGuard<'a, Option<f64>>,
+    thumbnail_url: Option<&'a str>,
+    fields: RwLockReadGuard<'a, VideoState>,
 }
 
 static RE_OUTPUT_FILE_DESTINATION: Lazy<Regex> = Lazy::new(|| {
@@ -69,10 +253,40 @@ static RE_OUTPUT_FILE_MERGING: Lazy<Regex> = Lazy::new(|| {
 });
 
 static RE_PERCENT_DONE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^\[download\]\s+(?P<percent_done>[\d+\.]+?)%").unwrap());
+    Lazy::new(|| Regex::new(r"^\[download\]\s+(?P<percent_done>\d+(?:\.\d+)?)%").unwrap());
+
+static RE_SUBTITLE_FILE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[info\] Writing video (?:automatic )?subtitles to: (?P<subtitle_file>.+)$")
+        .unwrap()
+});
+
+static RE_GEO_BLOCKED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^ERROR: (?P<geo_block_reason>.*(?:available in your country|available from your location|geo[- ]?restrict).*)$",
+    )
+    .unwrap()
+});
+
+static RE_SKIPPED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\[download\] (?P<skip_reason>File is larger than max-filesize.*?)\.?(?: Aborting\.)?$",
+    )
+    .unwrap()
+});
 
-static REGEX_DOWNLOAD_PROGRESS: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^\[download\]\s+(?P<percent>[\d+\.]+?)% of\s+(?P<size>(?:~\s*)?[\d+\.]+?(?:[KMG]i)B)(?: at\s+(?P<speed>(?:(?:~\s*)?[\d+\.]+?(?:[KMG]i)?|Unknown )B/s))?(?: ETA\s+(?P<eta>(?:[\d:-]+|Unknown)))?(?: \(frag (?P<frag>\d+)/(?P<frag_total>\d+)\))?").unwrap()
+// Matches the downloader's own retries, e.g.:
+// - `[download] Got error: HTTP Error 503: Service Unavailable. Retrying fragment 12 (1/10)...` (`yt-dlp`)
+// - `[download] Got error: The read operation timed out. Retrying (2/10)...` (`yt-dlp`)
+// - `[download] Got server HTTP error: ... Retrying (attempt 1 of 10)...` (`youtube-dl`)
+static RE_DOWNLOADER_RETRY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"Retrying(?: fragment \d+)? \((?:attempt )?(?P<retry>\d+)(?:/| of )(?P<retries>\d+|inf)\)",
+    )
+    .unwrap()
+});
+
+static RE_RESUMING: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[download\] Resuming download at byte (?P<resumed_at_byte>\d+)$").unwrap()
 });
 
 impl Video {
@@ -91,14 +305,100 @@ impl Video {
         title: Option<String>,
     ) -> Self {
         Self {
-            stage: RwLock::new(Stage::Initializing),
+            index: OnceLock::new(),
             url: url.into(),
             referer: referer.map(Into::into),
-            title: RwLock::new(title),
-            line: RwLock::new(None),
-            output_file: RwLock::new(None),
-            percent_done: RwLock::new(None),
+            thumbnail_url: None,
+            fetch_title: None,
+
+            fields: RwLock::new(VideoState {
+                stage: Stage::Initializing,
+                title,
+                line: None,
+                output_file: None,
+                percent_done: None,
+                elapsed: None,
+                last_progress_at: Instant::now(),
+                attempt: 1,
+                downloader_retry: None,
+                final_size: None,
+                command: None,
+                resumed_at_byte: None,
+                subtitle_files: vec![],
+                skip_reason: None,
+                geo_block_reason: None,
+                downloader_error: None,
+                simulated: false,
+                merging: false,
+                receiving_subtitles: false,
+            }),
+
+            line_throttle: Mutex::new(LineThrottle::default()),
+
+            cancellation: Notify::new(),
+
+            events: OnceLock::new(),
+        }
+    }
+
+    // Start building a video from its URL, e.g. to have its title fetched alongside the download.
+    pub(crate) fn from_url(url: impl Into<String>) -> VideoBuilder {
+        VideoBuilder {
+            url: url.into(),
+            referer: None,
+            title: None,
+            fetch_title: None,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn with_thumbnail_url(mut self, thumbnail_url: Option<String>) -> Self {
+        self.thumbnail_url = thumbnail_url;
+        self
+    }
+
+    pub(crate) fn set_index(&self, index: usize) {
+        // A video is only pushed to the application state once.
+        self.index.set(index).ok();
+    }
+
+    pub(crate) fn index(&self) -> Option<usize> {
+        self.index.get().copied()
+    }
+
+    pub(crate) fn attach_events(&self, events: broadcast::Sender<Event>) {
+        // A video is only pushed to the application state once.
+        self.events.set(events).ok();
+    }
+
+    fn publish(&self, event: Event) {
+        if let Some(events) = self.events.get() {
+            // Sending only fails if nobody is subscribed, which is fine.
+            events.send(event).ok();
+        }
+    }
+
+    async fn publish_stage(&self) {
+        let stage = self.stage().await.public();
+        self.publish(Event::VideoStage {
+            url: self.url.clone(),
+            stage,
+        });
+    }
+
+    // Queue the download, unless cancelled before.
+    #[instrument]
+    pub(crate) async fn set_stage_queued(&self) {
+        let mut fields = self.fields.write().await;
+        if matches!(fields.stage, Stage::Cancelled) {
+            return;
         }
+        fields.stage = Stage::Queued;
+        drop(fields);
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, "Download queued.");
     }
 
     #[instrument]
@@ -107,36 +407,131 @@ impl Video {
         process_id: u32,
         shutdown_signal: oneshot::Receiver<()>,
     ) {
-        *self.stage.write().await = Stage::Running {
-            process_id,
-            shutdown_signal: Some(shutdown_signal),
-        };
+        let previous_stage = self
+            .replace_stage(Stage::Running {
+                process_id,
+                shutdown_signal: Some(shutdown_signal),
+            })
+            .await;
+        if !previous_stage.is_active() {
+            metrics::download_started();
+        }
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, process_id, "Download started.");
     }
 
     #[instrument]
     pub(crate) async fn set_stage_shutting_down(&self) {
-        *self.stage.write().await = Stage::ShuttingDown;
+        if self.replace_stage(Stage::ShuttingDown).await.is_active() {
+            metrics::download_stopped();
+        }
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, "Download shutting down.");
     }
 
     #[instrument]
     pub(crate) async fn set_stage_finished(&self) {
-        *self.stage.write().await = Stage::Finished;
+        if self.replace_stage(Stage::Finished).await.is_active() {
+            metrics::download_stopped();
+        }
+        metrics::download_completed();
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        let output_file = self.output_file().await.clone().unwrap_or_default();
+        let elapsed_secs = self.elapsed_secs_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, %output_file, elapsed_secs, "Download finished.");
+    }
+
+    #[instrument]
+    pub(crate) async fn set_stage_skipped(&self, reason: String) {
+        if self
+            .replace_stage(Stage::Skipped {
+                reason: reason.clone(),
+            })
+            .await
+            .is_active()
+        {
+            metrics::download_stopped();
+        }
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, %reason, "Download skipped.");
+    }
+
+    #[instrument]
+    pub(crate) async fn set_stage_geo_blocked(&self, reason: String) {
+        if self
+            .replace_stage(Stage::GeoBlocked {
+                reason: reason.clone(),
+            })
+            .await
+            .is_active()
+        {
+            metrics::download_stopped();
+        }
+        metrics::download_failed();
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, %reason, "Download geo-blocked.");
     }
 
     #[instrument]
     pub(crate) async fn set_stage_failed(&self) {
-        *self.stage.write().await = Stage::Failed;
+        if self.replace_stage(Stage::Failed).await.is_active() {
+            metrics::download_stopped();
+        }
+        metrics::download_failed();
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        let elapsed_secs = self.elapsed_secs_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, elapsed_secs, "Download failed.");
     }
 
-    pub(crate) async fn stage(&self) -> RwLockReadGuard<Stage> {
-        self.stage.read().await
+    // Set a new stage, returning the previous one.
+    // Starting a download (again) also resets its progress timer and the downloader's retries.
+    async fn replace_stage(&self, stage: Stage) -> Stage {
+        let mut fields = self.fields.write().await;
+        if matches!(stage, Stage::Running { .. }) {
+            fields.last_progress_at = Instant::now();
+            fields.downloader_retry = None;
+            fields.downloader_error = None;
+            fields.merging = false;
+        }
+        std::mem::replace(&mut fields.stage, stage)
+    }
+
+    // Title as recorded on stage transition events, empty if unknown.
+    async fn title_field(&self) -> String {
+        self.title().await.clone().unwrap_or_default()
+    }
+
+    // Download duration as recorded on stage transition events, zero if unknown.
+    async fn elapsed_secs_field(&self) -> f64 {
+        self.elapsed()
+            .await
+            .map_or(0.0, |elapsed| elapsed.as_secs_f64())
+    }
+
+    pub(crate) async fn stage(&self) -> RwLockReadGuard<'_, Stage> {
+        RwLockReadGuard::map(self.fields.read().await, |fields| &fields.stage)
     }
 
     #[instrument]
     pub(crate) async fn take_shutdown_signal(&self) -> Option<oneshot::Receiver<()>> {
-        match &mut *self.stage.write().await {
+        match &mut self.fields.write().await.stage {
             Stage::Running {
                 shutdown_signal, ..
+            }
+            | Stage::Paused {
+                shutdown_signal, ..
             } => shutdown_signal.take(),
             _ => None,
         }
@@ -146,142 +541,356 @@ impl Video {
         &self.url
     }
 
+    pub(crate) fn referer(&self) -> Option<&str> {
+        self.referer.as_deref()
+    }
+
     pub(crate) async fn use_title<F, O>(&self, f: F) -> O
     where
         F: FnOnce(&Option<String>) -> O,
     {
-        let title = self.title.read().await;
-        f(&title)
+        let fields = self.fields.read().await;
+        f(&fields.title)
     }
 
     pub(crate) async fn update_title(&self, new_title: String) {
-        let mut title = self.title.write().await;
-        *title = Some(new_title);
+        self.publish(Event::VideoTitle {
+            url: self.url.clone(),
+            title: new_title.clone(),
+        });
+
+        self.fields.write().await.title = Some(new_title);
     }
 
-    pub(crate) async fn title(&self) -> RwLockReadGuard<Option<String>> {
-        self.title.read().await
+    pub(crate) async fn title(&self) -> RwLockReadGuard<'_, Option<String>> {
+        RwLockReadGuard::map(self.fields.read().await, |fields| &fields.title)
     }
 
-    pub(crate) async fn update_line(&self, new_line: String) {
-        self.extract_output_file(&new_line).await;
-        self.extract_percent_done(&new_line).await;
+    pub(crate) async fn update_line(&self, state: &State, new_line: String) {
+        let Some(min_interval) = state.line_throttle else {
+            return self.apply_lines(state, [new_line]).await;
+        };
 
-        // Store the line to ref to it for size, speed and ETA ranges.
-        let mut line = self.line.write().await;
-        *line = Some(new_line);
-    }
-
-    async fn extract_output_file(&self, line: &str) {
-        // Extract output file if present in the current line
-        let maybe_captures = RE_OUTPUT_FILE_DESTINATION
-            .captures(line)
-            .or_else(|| RE_OUTPUT_FILE_ALREADY_DOWNLOADED.captures(line))
-            .or_else(|| RE_OUTPUT_FILE_MERGING.captures(line));
-        if let Some(captures) = maybe_captures {
-            if let Some(output_file) = captures
-                .name("output_file")
-                .map(|output_file_match| output_file_match.as_str().into())
-            {
-                self.update_output_file(output_file).await;
+        // Progress lines arriving faster than the throttle interval are coalesced, keeping only the latest,
+        // so that fast downloads do not contend for the line and progress locks with rendering.
+        let pending_line = {
+            let mut throttle = self
+                .line_throttle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+
+            if progress::is_progress_line(&new_line) {
+                if throttle
+                    .applied_at
+                    .is_some_and(|applied_at| applied_at.elapsed() < min_interval)
+                {
+                    throttle.pending = Some(new_line);
+                    return;
+                }
+
+                throttle.applied_at = Some(Instant::now());
+                // Superseded by the new line.
+                throttle.pending = None;
+                None
+            } else {
+                throttle.pending.take()
             }
+        };
+
+        // A held back progress line is applied before any later line, preserving the order of lines.
+        self.apply_lines(state, pending_line.into_iter().chain([new_line]))
+            .await;
+    }
+
+    // Apply the progress line held back by `--throttle-ui`, if any, once the downloader's output ends.
+    async fn flush_line(&self, state: &State) {
+        let pending_line = self
+            .line_throttle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pending
+            .take();
+
+        if let Some(pending_line) = pending_line {
+            self.apply_lines(state, [pending_line]).await;
         }
     }
 
-    async fn extract_percent_done(&self, line: &str) {
-        // Extract current percent done if present in the current line
-        let maybe_captures = RE_PERCENT_DONE.captures(line);
-        if let Some(captures) = maybe_captures {
-            if let Some(percent_done) = captures
-                .name("percent_done")
-                .and_then(|percent_done_match| percent_done_match.as_str().parse::<f64>().ok())
-            {
-                self.update_percent_done(percent_done).await;
+    // Parse the lines before acquiring the write guard, then apply all of their changes at once, in order.
+    async fn apply_lines(&self, state: &State, new_lines: impl IntoIterator<Item = String>) {
+        let new_lines: Vec<ParsedLine> = new_lines.into_iter().map(ParsedLine::parse).collect();
+
+        let mut fields = self.fields.write().await;
+        for new_line in &new_lines {
+            self.apply_line(state, &mut fields, new_line);
+        }
+        drop(fields);
+
+        for new_line in new_lines {
+            self.publish(Event::VideoLine {
+                url: self.url.clone(),
+                line: new_line.line,
+            });
+        }
+    }
+
+    fn apply_line(&self, state: &State, fields: &mut VideoState, new_line: &ParsedLine) {
+        if new_line.is_merging {
+            fields.merging = true;
+        }
+
+        if let Some(ref subtitle_file) = new_line.subtitle_file {
+            fields.subtitle_files.push(subtitle_file.clone());
+        }
+
+        if let Some(ref output_file) = new_line.output_file {
+            self.apply_output_file(state, fields, output_file);
+        }
+
+        // Subtitle downloads report their own progress, which is not the video's.
+        if !fields.receiving_subtitles {
+            if let Some(percent_done) = new_line.percent_done {
+                fields.update_percent_done(percent_done);
+
+                // Progress resumed, so the downloader's latest retry succeeded.
+                fields.downloader_retry = None;
             }
+
+            if new_line.final_size.is_some() {
+                fields.final_size.clone_from(&new_line.final_size);
+            }
+        }
+
+        if new_line.downloader_retry.is_some() {
+            fields
+                .downloader_retry
+                .clone_from(&new_line.downloader_retry);
         }
+        if new_line.resumed_at_byte.is_some() {
+            fields.resumed_at_byte = new_line.resumed_at_byte;
+        }
+        if new_line.skip_reason.is_some() {
+            fields.skip_reason.clone_from(&new_line.skip_reason);
+        }
+        if new_line.geo_block_reason.is_some() {
+            fields
+                .geo_block_reason
+                .clone_from(&new_line.geo_block_reason);
+        }
+        if new_line.is_error {
+            fields.downloader_error = Some(new_line.line.clone());
+        }
+
+        // Store the line to ref to it for size, speed and ETA ranges.
+        fields.line = Some(new_line.line.clone());
+    }
+
+    fn apply_output_file(&self, state: &State, fields: &mut VideoState, output_file: &str) {
+        // Subtitle files are announced before their download starts.
+        let is_subtitle_file = fields.subtitle_files.iter().any(|file| file == output_file);
+        fields.receiving_subtitles = is_subtitle_file;
+        if is_subtitle_file {
+            return;
+        }
+
+        // Concurrent downloads into the same file, e.g. of different videos sharing a title,
+        // clobber each other's output.
+        if let Some(claimed_by) = state.claim_output_file(output_file, &self.url) {
+            warn!(
+                url = %self.url,
+                %claimed_by,
+                %output_file,
+                "Output file collision! Another download is writing to the same output file."
+            );
+            state.push_notice(format!(
+                "'{}' and '{claimed_by}' are both writing to '{output_file}' (hint: add '%(id)s' to the '--output-template')",
+                self.url
+            ));
+        }
+
+        fields.output_file = Some(output_file.to_owned());
     }
 
-    pub(crate) async fn line(&self) -> RwLockReadGuard<Option<String>> {
-        self.line.read().await
+    pub(crate) async fn output_file(&self) -> RwLockReadGuard<'_, Option<String>> {
+        RwLockReadGuard::map(self.fields.read().await, |fields| &fields.output_file)
     }
 
-    pub(crate) async fn update_percent_done(&self, new_percent: f64) {
-        let mut percent_done = self.percent_done.write().await;
-        *percent_done = Some(new_percent);
+    pub(crate) async fn update_elapsed(&self, new_elapsed: Duration) {
+        self.fields.write().await.elapsed = Some(new_elapsed);
     }
 
-    pub(crate) async fn percent_done(&self) -> RwLockReadGuard<Option<f64>> {
-        self.percent_done.read().await
+    pub(crate) async fn update_command(&self, new_command: String, simulated: bool) {
+        let mut fields = self.fields.write().await;
+        fields.command = Some(new_command);
+        fields.simulated = simulated;
     }
 
-    pub(crate) async fn update_output_file(&self, new_output_file: String) {
-        let mut output_file = self.output_file.write().await;
-        *output_file = Some(new_output_file);
+    // Start the given download attempt, discarding the progress and errors of the previous attempt.
+    pub(crate) async fn update_attempt(&self, new_attempt: u8) {
+        let mut fields = self.fields.write().await;
+        fields.attempt = new_attempt;
+        fields.line = None;
+        fields.percent_done = None;
+        fields.final_size = None;
+        fields.downloader_retry = None;
+        fields.resumed_at_byte = None;
+        fields.downloader_error = None;
+        fields.merging = false;
+        fields.receiving_subtitles = false;
     }
 
-    pub(crate) async fn output_file(&self) -> RwLockReadGuard<Option<String>> {
-        self.output_file.read().await
+    pub(crate) async fn elapsed(&self) -> RwLockReadGuard<'_, Option<Duration>> {
+        RwLockReadGuard::map(self.fields.read().await, |fields| &fields.elapsed)
     }
 
-    #[instrument(skip(state))]
     pub(crate) async fn download(self: Arc<Self>, state: Arc<State>) -> Result<()> {
+        let Some(fetch_title) = self.fetch_title else {
+            return self.download_video(state).await;
+        };
+
+        // The title is fetched even if the video is not downloaded, e.g. when only extracting videos.
+        // A failure to fetch the title does not cut the download short, nor fail the downloaded video.
+        debug!("Fetch title for '{}'...", self.url);
+        let (fetched, downloaded) = tokio::join!(
+            fetch_title(self.clone()),
+            self.clone().download_video(state),
+        );
+        downloaded?;
+        if let Err(e) = fetched {
+            warn!("Failed to fetch title for '{}': {e:?}", self.url);
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(state))]
+    async fn download_video(self: Arc<Self>, state: Arc<State>) -> Result<()> {
+        if state.extract_only {
+            debug!("Not downloading '{}', as only extracting videos.", self.url);
+            return Ok(());
+        }
+
         if state.is_shutting_down().await {
             warn!("Refusing to start a new download during shutdown.");
             // Not an error.
+            return self.cancel(state.shutdown_signal).await;
+        }
+
+        // Wait for a download slot, if the number of concurrent downloads is limited.
+        if state.limits_concurrent_downloads() {
+            self.set_stage_queued().await;
+        }
+        let _permit = tokio::select! {
+            permit = state.acquire_download_permit() => permit?,
+            () = self.cancellation.notified() => return Ok(()),
+        };
+
+        // Space out download starts, if requested. Pacing after acquiring a download slot
+        // keeps downloads spaced out with limited concurrency, too.
+        state.pace_download_start().await;
+
+        // Cancelled while not waiting for a download slot, e.g. without a concurrency limit.
+        if matches!(*self.stage().await, Stage::Cancelled) {
             return Ok(());
         }
 
-        let (signal_shutdown, shutdown_signal) = oneshot::channel();
+        if state.is_shutting_down().await {
+            warn!("Refusing to start a queued download during shutdown.");
+            // Not an error.
+            return self.cancel(state.shutdown_signal).await;
+        }
+
+        let started = Instant::now();
 
-        let cmd = format!(
-            "{} --newline --no-colors{} {} '{}'",
-            state.downloader,
-            self.referer
-                .as_ref()
-                .map(|referer| { format!(" --add-header 'Referer:{}'", &referer) })
-                .unwrap_or_default(),
-            state.downloader_options.join(" "),
-            self.url()
-        );
+        let downloader_options = substitute_url(&state.downloader_options, self.url());
 
-        debug!("Spawn: {cmd}");
-        let child_exit = self
-            .clone()
-            .child_read_to_end({
-                let mut command = Command::new(&*state.downloader);
-
-                command
-                    .kill_on_drop(true)
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .arg("--newline")
-                    .arg("--no-colors")
-                    .arg("--legacy-server-connect");
-
-                if let Some(ref referer) = self.referer {
-                    command
-                        .arg("--add-header")
-                        .arg(format!("Referer:{referer}"));
-                }
+        let mut attempt = 1;
+        let (child_exit, signal_shutdown) = loop {
+            self.update_attempt(attempt).await;
 
-                let child = command
-                    .args(&*state.downloader_options)
-                    .arg(self.url())
-                    .spawn()
-                    .wrap_err_with(|| format!("Command failed to start: {cmd}"))?;
+            let (signal_shutdown, shutdown_signal) = oneshot::channel();
 
-                if let Some(process_id) = child.id() {
-                    self.set_stage_running(process_id, shutdown_signal).await;
-                }
+            let mut command = self.download_command(&state, &downloader_options);
+            let cmd = display_command(&command);
 
-                child
-            })
-            .await;
+            debug!("Spawn: {cmd}");
+            self.update_command(cmd.clone(), state.simulate).await;
+            let child_exit = self
+                .clone()
+                .child_read_to_end(state.clone(), {
+                    let child = command
+                        .spawn()
+                        .wrap_err_with(|| format!("Command failed to start: {cmd}"))?;
+
+                    if let Some(process_id) = child.id() {
+                        self.set_stage_running(process_id, shutdown_signal).await;
+
+                        // Downloads starting while all downloads are paused are paused right away.
+                        if state.is_paused() {
+                            self.pause().await?;
+                        }
+                    }
+
+                    child
+                })
+                .await;
+
+            // Retry failed downloads, unless all attempts are used up,
+            // or the user requested shutdown or interrupted this download.
+            // Skipped and geo-blocked videos would fail the same way again.
+            if child_exit.is_err()
+                && attempt < state.download_attempts
+                && !state.is_shutting_down().await
+                && self.is_retryable().await
+            {
+                warn!(
+                    "'{}' failed. Retrying now. (attempt {}/{})",
+                    self.url,
+                    attempt + 1,
+                    state.download_attempts
+                );
+                attempt += 1;
+                continue;
+            }
+
+            break (child_exit, signal_shutdown);
+        };
+
+        self.update_elapsed(started.elapsed()).await;
+        state.release_output_files(&self.url);
 
-        if let Err(report) = child_exit {
-            error!("'{}' failed: {:?}", self.url, report);
-            self.set_stage_failed().await;
+        // Skipped videos are no error, regardless of the downloader's exit status.
+        let (maybe_skip_reason, maybe_geo_block_reason, maybe_downloader_error) = {
+            let mut fields = self.fields.write().await;
+            (
+                fields.skip_reason.take(),
+                fields.geo_block_reason.take(),
+                fields.downloader_error.take(),
+            )
+        };
+        if let Some(skip_reason) = maybe_skip_reason {
+            info!("'{}' skipped: {skip_reason}", self.url);
+            self.set_stage_skipped(skip_reason).await;
+        } else if let Err(report) = child_exit {
+            if let Some(geo_block_reason) = maybe_geo_block_reason {
+                error!("'{}' is geo-blocked: {geo_block_reason}", self.url);
+                self.set_stage_geo_blocked(geo_block_reason).await;
+            } else {
+                // The downloader's own error line explains the failure better than its exit status.
+                let report = match maybe_downloader_error {
+                    Some(downloader_error) => report.wrap_err(downloader_error),
+                    None => report,
+                };
+                error!("'{}' failed: {:?}", self.url, report);
+                self.set_stage_failed().await;
+            }
         } else {
+            if let Some(downloader_error) = maybe_downloader_error {
+                info!(
+                    "'{}' finished, despite the downloader reporting '{downloader_error}'.",
+                    self.url
+                );
+            }
             info!("'{}' finished.", self.url);
             self.set_stage_finished().await;
         }
@@ -300,17 +909,139 @@ impl Video {
         Ok(())
     }
 
-    #[instrument]
-    async fn child_read_to_end(self: Arc<Self>, mut child: Child) -> Result<()> {
+    // The downloader command of a download attempt, with the video URL as last argument.
+    fn download_command(&self, state: &State, downloader_options: &[String]) -> Command {
+        let mut command = state.downloader_request_command(self.referer());
+
+        command
+            .kill_on_drop(true)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .arg("--newline")
+            .arg("--no-colors")
+            .arg("--legacy-server-connect");
+
+        // Resume partially downloaded files, e.g. left by a previous shutdown, rather than restarting.
+        command.arg(if state.continue_partial_downloads {
+            "--continue"
+        } else {
+            "--no-continue"
+        });
+
+        if let Some(ref limit_rate) = state.limit_rate {
+            command.arg("--limit-rate").arg(limit_rate);
+        }
+
+        if let Some(ref max_filesize) = state.max_filesize {
+            command.arg("--max-filesize").arg(max_filesize);
+        }
+
+        // Before free-form downloader options, which may still override the template.
+        if let Some(ref output_template) = state.output_template {
+            command.arg("-o").arg(output_template);
+        }
+
+        if state.windows_filenames {
+            command.arg("--windows-filenames");
+        }
+
+        if state.embed_metadata {
+            command.arg("--embed-metadata");
+        }
+
+        if state.embed_thumbnail {
+            command.arg("--embed-thumbnail");
+        }
+
+        if state.subtitles {
+            command.arg("--write-subs").arg("--sub-langs").arg("all");
+
+            if state.auto_subtitles {
+                command.arg("--write-auto-subs");
+            }
+        }
+
+        if let Some(ref retry_sleep) = state.retry_sleep {
+            command.arg("--retry-sleep").arg(retry_sleep);
+        }
+
+        if let Some(trim_filenames) = state.trim_filenames {
+            command
+                .arg("--trim-filenames")
+                .arg(trim_filenames.to_string());
+        }
+
+        if let Some(concurrent_fragments) = state.concurrent_fragments {
+            command
+                .arg("--concurrent-fragments")
+                .arg(concurrent_fragments.to_string());
+        }
+
+        if state.simulate {
+            command.arg("--simulate");
+        }
+
+        command.args(downloader_options).arg(self.url());
+        command
+    }
+
+    // Whether a failed download may be retried, unless interrupted by the user.
+    // Skipped and geo-blocked videos would fail the same way again.
+    async fn is_retryable(&self) -> bool {
+        let fields = self.fields.read().await;
+        !matches!(fields.stage, Stage::ShuttingDown)
+            && fields.skip_reason.is_none()
+            && fields.geo_block_reason.is_none()
+    }
+
+    // Resolve the direct media URLs of the video, without downloading it.
+    // Videos with separate video and audio streams resolve to one URL per stream.
+    #[instrument(skip(state))]
+    pub(crate) async fn resolve_media_urls(&self, state: &State) -> Result<Vec<String>> {
+        let mut command = state.downloader_request_command(self.referer());
+
+        command
+            .kill_on_drop(true)
+            .stdin(Stdio::null())
+            .arg("--no-colors")
+            .arg("--legacy-server-connect")
+            .arg("--get-url");
+
+        let output = command
+            .args(substitute_url(&state.downloader_options, self.url()))
+            .arg(self.url())
+            .output()
+            .await
+            .wrap_err_with(|| format!("Downloader failed to resolve '{}'", self.url))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(eyre!(
+                "Downloader failed to resolve '{}': {}",
+                self.url,
+                stderr.lines().last().unwrap_or("no error output")
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+
+    #[instrument(skip(state))]
+    async fn child_read_to_end(self: Arc<Self>, state: Arc<State>, mut child: Child) -> Result<()> {
         let consume_stdout = child
             .stdout
             .take()
-            .map(|stdout| self.clone().consume_stream(stdout));
+            .map(|stdout| self.clone().consume_stream(state.clone(), stdout));
 
         let consume_stderr = child
             .stderr
             .take()
-            .map(|stderr| self.clone().consume_stream(stderr));
+            .map(|stderr| self.clone().consume_stream(state.clone(), stderr));
 
         let await_exit = async {
             tokio::spawn(
@@ -347,33 +1078,41 @@ impl Video {
         Ok(())
     }
 
-    #[instrument]
+    #[instrument(skip(state))]
     fn consume_stream<A: AsyncRead + Unpin + Send + 'static + Debug>(
         self: Arc<Self>,
+        state: Arc<State>,
         reader: A,
     ) -> JoinHandle<Result<()>> {
-        let mut lines = BufReader::new(reader).lines();
+        // Split raw bytes, rather than reading `String` lines, which fail on the first invalid UTF-8 byte,
+        // e.g. of a file name in a non-UTF-8 locale.
+        let mut segments = BufReader::new(reader).split(b'\n');
 
         let video = self;
         tokio::spawn(
             async move {
-                while let Some(next_line) = lines.next_line().await? {
+                while let Some(segment) = segments.next_segment().await? {
+                    let next_line = decode_line(&segment);
                     video
                         .use_title(|title| {
                             let title = match *title {
                                 Some(ref title) => title,
                                 None => video.url(),
                             };
-                            if next_line.starts_with("ERROR:") {
-                                error!("Line from '{title}': '{next_line}'");
-                            } else {
-                                trace!("Line from '{title}': '{next_line}'");
+                            // Only the exit status tells whether the download failed.
+                            match line_severity(&next_line) {
+                                LineSeverity::Error | LineSeverity::Warning => {
+                                    warn!("Line from '{title}': '{next_line}'");
+                                }
+                                LineSeverity::Info => trace!("Line from '{title}': '{next_line}'"),
                             }
                         })
                         .await;
 
-                    video.update_line(next_line).await;
+                    video.update_line(&state, next_line).await;
+                    state.record_activity();
                 }
+                video.flush_line(&state).await;
 
                 Ok::<(), Report>(())
             }
@@ -381,107 +1120,1352 @@ impl Video {
         )
     }
 
-    // Acquire read guards for all fine-grained access-controlled fields.
-    pub(crate) async fn read(&self) -> VideoRead {
+    // Acquire a read guard for all mutable fields at once.
+    pub(crate) async fn read(&self) -> VideoRead<'_> {
         VideoRead {
-            stage: self.stage().await,
+            index: self.index(),
             url: &self.url,
-            title: self.title().await,
-            line: self.line().await,
-            output_file: self.output_file().await,
-            percent_done: self.percent_done().await,
+            thumbnail_url: self.thumbnail_url.as_deref(),
+            fields: self.fields.read().await,
+        }
+    }
+
+    // Cancel a queued or not yet started download, so that it never starts. A running download is interrupted instead.
+    #[instrument]
+    pub(crate) async fn cancel(&self, shutdown_signal: ShutdownSignal) -> Result<()> {
+        let mut fields = self.fields.write().await;
+        if !matches!(fields.stage, Stage::Initializing | Stage::Queued) {
+            drop(fields);
+            return self.initiate_shutdown(shutdown_signal).await;
         }
+        fields.stage = Stage::Cancelled;
+        drop(fields);
+
+        self.cancellation.notify_one();
+        self.publish_stage().await;
+
+        let title = self.title_field().await;
+        info!(index = self.index().unwrap_or_default(), url = %self.url, %title, "Download cancelled.");
+
+        Ok(())
     }
 
     #[instrument]
-    pub(crate) async fn initiate_shutdown(&self) -> Result<()> {
+    pub(crate) async fn initiate_shutdown(&self, shutdown_signal: ShutdownSignal) -> Result<()> {
         // Get process ID - if available - then drop the read guard.
-        let maybe_process_id = match *self.stage().await {
-            Stage::Running { process_id, .. } => Some(process_id),
+        let maybe_process = match *self.stage().await {
+            Stage::Running { process_id, .. } => Some((process_id, false)),
+            Stage::Paused { process_id, .. } => Some((process_id, true)),
             _ => None,
         };
 
         // Use the process ID - if available - acquiring a write guard.
-        if let Some(process_id) = maybe_process_id {
+        if let Some((process_id, is_paused)) = maybe_process {
             debug!("Shutting down child process {process_id}.");
 
             self.set_stage_shutting_down().await;
 
-            // Assert non-zero process ID, as for `kill 0`, the signal will be sent
-            // to all processes whose group ID is equal to the process group ID of the sender.
-            let non_zero: NonZeroU32 = process_id.try_into()?;
+            let signal = match shutdown_signal {
+                ShutdownSignal::Sigint => Signal::SIGINT,
+                ShutdownSignal::Sigterm => Signal::SIGTERM,
+            };
+            trace!("Sending {signal} to child process {process_id}.");
+            send_signal(process_id, signal)?;
+
+            // A stopped process handles the pending signal only once continued.
+            if is_paused {
+                trace!("Sending SIGCONT to child process {process_id}.");
+                send_signal(process_id, Signal::SIGCONT)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Stop a running child process, keeping it alive to be resumed.
+    #[instrument]
+    pub(crate) async fn pause(&self) -> Result<()> {
+        let mut fields = self.fields.write().await;
+        if let Stage::Running {
+            process_id,
+            shutdown_signal,
+        } = &mut fields.stage
+        {
+            trace!("Sending SIGSTOP to child process {process_id}.");
+            send_signal(*process_id, Signal::SIGSTOP)?;
+
+            fields.stage = Stage::Paused {
+                process_id: *process_id,
+                shutdown_signal: shutdown_signal.take(),
+            };
+            drop(fields);
+            self.publish_stage().await;
+        }
 
-            // Safely truncate u32 to i32.
-            let raw_pid: i32 = non_zero.get().try_into()?;
+        Ok(())
+    }
 
-            trace!("Sending SIGINT to child process {raw_pid}.");
-            signal::kill(Pid::from_raw(raw_pid), Signal::SIGINT)?;
+    // Continue a paused child process.
+    #[instrument]
+    pub(crate) async fn resume(&self) -> Result<()> {
+        let mut fields = self.fields.write().await;
+        if let Stage::Paused {
+            process_id,
+            shutdown_signal,
+        } = &mut fields.stage
+        {
+            trace!("Sending SIGCONT to child process {process_id}.");
+            send_signal(*process_id, Signal::SIGCONT)?;
+
+            fields.stage = Stage::Running {
+                process_id: *process_id,
+                shutdown_signal: shutdown_signal.take(),
+            };
+            // Time spent paused does not count as stalled.
+            fields.last_progress_at = Instant::now();
+            drop(fields);
+            self.publish_stage().await;
         }
 
         Ok(())
     }
 }
 
+fn send_signal(process_id: u32, signal: Signal) -> Result<()> {
+    // Assert non-zero process ID, as for `kill 0`, the signal will be sent
+    // to all processes whose group ID is equal to the process group ID of the sender.
+    let non_zero: NonZeroU32 = process_id.try_into()?;
+
+    // Safely truncate u32 to i32.
+    let raw_pid: i32 = non_zero.get().try_into()?;
+
+    signal::kill(Pid::from_raw(raw_pid), signal)?;
+
+    Ok(())
+}
+
+// Decode a downloader output line, replacing invalid UTF-8 with replacement characters.
+fn decode_line(segment: &[u8]) -> String {
+    let segment = segment.strip_suffix(b"\r").unwrap_or(segment);
+    String::from_utf8_lossy(segment).into_owned()
+}
+
+// Extract current percent done if present in the current line.
+// Lines announcing a completed download count as 100 % done, even without a percentage.
+fn parse_percent_done(line: &str) -> Option<f64> {
+    if RE_OUTPUT_FILE_ALREADY_DOWNLOADED.is_match(line) || RE_OUTPUT_FILE_MERGING.is_match(line) {
+        return Some(100.0);
+    }
+
+    RE_PERCENT_DONE
+        .captures(line)?
+        .name("percent_done")?
+        .as_str()
+        .parse::<f64>()
+        .ok()
+}
+
+fn line_severity(line: &str) -> LineSeverity {
+    if line.starts_with("ERROR:") {
+        LineSeverity::Error
+    } else if line.starts_with("WARNING:") {
+        LineSeverity::Warning
+    } else {
+        LineSeverity::Info
+    }
+}
+
+// Extract the output file announced by the downloader, if any.
+fn parse_output_file(line: &str) -> Option<&str> {
+    let captures = RE_OUTPUT_FILE_DESTINATION
+        .captures(line)
+        .or_else(|| RE_OUTPUT_FILE_ALREADY_DOWNLOADED.captures(line))
+        .or_else(|| RE_OUTPUT_FILE_MERGING.captures(line))?;
+
+    Some(captures.name("output_file")?.as_str())
+}
+
+// Extract the subtitle file announced by the downloader, if any.
+fn parse_subtitle_file(line: &str) -> Option<&str> {
+    Some(
+        RE_SUBTITLE_FILE
+            .captures(line)?
+            .name("subtitle_file")?
+            .as_str(),
+    )
+}
+
+// Extract the reason for the downloader intentionally not downloading a video.
+fn parse_skip_reason(line: &str) -> Option<&str> {
+    Some(RE_SKIPPED.captures(line)?.name("skip_reason")?.as_str())
+}
+
+// Extract the downloader's geo-restriction message.
+fn parse_geo_block_reason(line: &str) -> Option<&str> {
+    Some(
+        RE_GEO_BLOCKED
+            .captures(line)?
+            .name("geo_block_reason")?
+            .as_str(),
+    )
+}
+
+// Extract the downloader's own retry count, as `retry/retries`.
+fn parse_downloader_retry(line: &str) -> Option<String> {
+    let captures = RE_DOWNLOADER_RETRY.captures(line)?;
+
+    Some(format!(
+        "{}/{}",
+        captures.name("retry")?.as_str(),
+        captures.name("retries")?.as_str()
+    ))
+}
+
+// Extract the byte offset at which the downloader resumes a partially downloaded file.
+fn parse_resumed_at_byte(line: &str) -> Option<u64> {
+    RE_RESUMING
+        .captures(line)?
+        .name("resumed_at_byte")?
+        .as_str()
+        .parse()
+        .ok()
+}
+
 impl<'a> VideoRead<'a> {
+    pub(crate) fn index(&self) -> Option<usize> {
+        self.index
+    }
+
     pub(crate) fn stage(&self) -> &Stage {
-        &self.stage
+        &self.fields.stage
     }
 
     pub(crate) fn url(&self) -> &'a str {
         self.url
     }
 
-    pub(crate) fn title(&self) -> &Option<String> {
-        &self.title
+    pub(crate) fn thumbnail_url(&self) -> Option<&'a str> {
+        self.thumbnail_url
+    }
+
+    pub(crate) fn title(&self) -> Option<&String> {
+        self.fields.title.as_ref()
     }
 
     pub(crate) fn progress_detail(&'a self) -> Option<ProgressDetail<'a>> {
-        match *self.line {
-            Some(ref line) => {
-                let maybe_captures = REGEX_DOWNLOAD_PROGRESS.captures(line.as_str());
-                match maybe_captures {
-                    Some(captures) => {
-                        let percent = captures
-                            .name("percent")
-                            .and_then(|percent_match| percent_match.as_str().parse::<f64>().ok())
-                            // Fall back to last stored progress percentage if current line does not provide a fresh value.
-                            .or(*self.percent_done);
-
-                        let size = captures.name("size").map(|size_match| size_match.range());
-                        let speed = captures
-                            .name("speed")
-                            .map(|speed_match| speed_match.range());
-                        let eta = captures.name("eta").map(|eta_match| eta_match.range());
-
-                        let frag = captures
-                            .name("frag")
-                            .and_then(|frag_match| frag_match.as_str().parse::<u16>().ok());
-
-                        let frag_total = captures.name("frag_total").and_then(|frag_total_match| {
-                            frag_total_match.as_str().parse::<u16>().ok()
-                        });
-                        Some(ProgressDetail::Parsed {
-                            line,
-                            percent,
-                            size,
-                            speed,
-                            eta,
-                            frag,
-                            frag_total,
-                        })
-                    }
-                    None => Some(ProgressDetail::Raw(line)),
-                }
+        self.fields
+            .line
+            .as_deref()
+            .map(|line| ProgressDetail::parse(line, self.fields.percent_done))
+    }
+
+    pub(crate) fn output_file(&self) -> Option<&String> {
+        self.fields.output_file.as_ref()
+    }
+
+    pub(crate) fn percent_done(&self) -> Option<f64> {
+        self.fields.percent_done
+    }
+
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        self.fields.elapsed
+    }
+
+    pub(crate) fn final_size(&self) -> Option<&String> {
+        self.fields.final_size.as_ref()
+    }
+
+    pub(crate) fn attempt(&self) -> u8 {
+        self.fields.attempt
+    }
+
+    pub(crate) fn downloader_retry(&self) -> Option<&str> {
+        self.fields.downloader_retry.as_deref()
+    }
+
+    pub(crate) fn last_progress_at(&self) -> Instant {
+        self.fields.last_progress_at
+    }
+
+    pub(crate) fn command(&self) -> Option<&String> {
+        self.fields.command.as_ref()
+    }
+
+    pub(crate) fn resumed_at_byte(&self) -> Option<u64> {
+        self.fields.resumed_at_byte
+    }
+
+    pub(crate) fn subtitle_files(&self) -> &[String] {
+        &self.fields.subtitle_files
+    }
+
+    // Whether the download was only simulated, in which case no output file is written.
+    pub(crate) fn is_simulated(&self) -> bool {
+        self.fields.simulated
+    }
+
+    // Whether the downloader is merging formats into the output file, until its process exits.
+    pub(crate) fn is_merging(&self) -> bool {
+        self.fields.merging && matches!(self.fields.stage, Stage::Running { .. })
+    }
+}
+
+impl VideoState {
+    fn update_percent_done(&mut self, new_percent: f64) {
+        if self.percent_done != Some(new_percent) {
+            self.last_progress_at = Instant::now();
+        }
+        self.percent_done = Some(new_percent);
+    }
+}
+
+impl Stage {
+    // Whether a child process is alive, running or paused.
+    fn is_active(&self) -> bool {
+        matches!(self, Stage::Running { .. } | Stage::Paused { .. })
+    }
+
+    // Process ID of the downloader, while its process is alive, running or paused.
+    pub(crate) fn process_id(&self) -> Option<u32> {
+        match self {
+            Stage::Running { process_id, .. } | Stage::Paused { process_id, .. } => {
+                Some(*process_id)
+            }
+            _ => None,
+        }
+    }
+
+    // Reason for skipping the video, if skipped.
+    pub(crate) fn skip_reason(&self) -> Option<&str> {
+        match self {
+            Stage::Skipped { reason } => Some(reason),
+            _ => None,
+        }
+    }
+
+    // Reason for the video being geo-blocked, if geo-blocked.
+    pub(crate) fn geo_block_reason(&self) -> Option<&str> {
+        match self {
+            Stage::GeoBlocked { reason } => Some(reason),
+            _ => None,
+        }
+    }
+
+    // The stage as published in events, without its details.
+    pub(crate) fn public(&self) -> VideoStage {
+        match self {
+            Stage::Initializing => VideoStage::Initializing,
+            Stage::Queued => VideoStage::Queued,
+            Stage::Running { .. } => VideoStage::Running,
+            Stage::Paused { .. } => VideoStage::Paused,
+            Stage::ShuttingDown => VideoStage::ShuttingDown,
+            Stage::Finished => VideoStage::Finished,
+            Stage::Failed => VideoStage::Failed,
+            Stage::Cancelled => VideoStage::Cancelled,
+            Stage::Skipped { .. } => VideoStage::Skipped,
+            Stage::GeoBlocked { .. } => VideoStage::GeoBlocked,
+        }
+    }
+
+    // Machine-readable stage name, e.g. for reports.
+    pub(crate) fn name(&self) -> &'static str {
+        self.public().name()
+    }
+}
+
+// Downloader options whose value is a secret.
+const SECRET_OPTIONS: [&str; 7] = [
+    "--password",
+    "-p",
+    "--video-password",
+    "--twofactor",
+    "-2",
+    "--ap-password",
+    "--client-certificate-password",
+];
+
+// Placeholder in downloader options, substituted with the video URL, e.g. in `--exec 'echo {url}'`.
+const URL_PLACEHOLDER: &str = "{url}";
+
+// Escaped placeholder, substituted with a literal `{url}`.
+const ESCAPED_URL_PLACEHOLDER: &str = "{{url}}";
+
+// Downloader options whose value is a shell command.
+const SHELL_COMMAND_OPTIONS: [&str; 2] = ["--exec", "--exec-before-download"];
+
+// Substitute the URL placeholder in each downloader option with the video URL.
+// Scraped URLs are untrusted, and may contain shell metacharacters, such as `;` or `$(...)`,
+// so the URL is shell-quoted within shell commands, e.g. of `--exec`.
+fn substitute_url(options: &[String], url: &str) -> Vec<String> {
+    let quoted_url = shell_quote(url);
+    let mut is_shell_command = false;
+    options
+        .iter()
+        .map(|option| {
+            // The shell command is either the next argument, or attached as `--exec=<command>`.
+            let url = if is_shell_command || is_inline_shell_command(option) {
+                &quoted_url
+            } else {
+                url
+            };
+            is_shell_command = SHELL_COMMAND_OPTIONS.contains(&option.as_str());
+
+            option
+                .split(ESCAPED_URL_PLACEHOLDER)
+                .map(|part| part.replace(URL_PLACEHOLDER, url))
+                .collect::<Vec<_>>()
+                .join(URL_PLACEHOLDER)
+        })
+        .collect()
+}
+
+fn is_inline_shell_command(option: &str) -> bool {
+    SHELL_COMMAND_OPTIONS.iter().any(|name| {
+        option
+            .strip_prefix(name)
+            .is_some_and(|command| command.starts_with('='))
+    })
+}
+
+// Render a command line for display, with secrets redacted, quoting arguments only where needed.
+fn display_command(command: &Command) -> String {
+    let command = command.as_std();
+    let args: Vec<String> = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+
+    std::iter::once(command.get_program().to_string_lossy().into_owned())
+        .chain(redact_downloader_options(&args))
+        .map(|arg| {
+            let is_plain = !arg.is_empty()
+                && arg
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphanumeric() || b"-_./:=,+@%".contains(&byte));
+            if is_plain {
+                arg
+            } else {
+                shell_quote(&arg)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Quote a value as a single POSIX shell word, escaping contained single quotes.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// Request headers whose value is a secret.
+const SECRET_HEADERS: [&str; 3] = ["cookie", "authorization", "proxy-authorization"];
+
+// Redact passwords and secret headers from downloader options, to display them in logs and the UI.
+fn redact_downloader_options(options: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(options.len());
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        redacted.push(option.clone());
+
+        if SECRET_OPTIONS.contains(&option.as_str()) {
+            if options.next().is_some() {
+                redacted.push(String::from("***"));
+            }
+        } else if option == "--add-header" {
+            if let Some(header) = options.next() {
+                redacted.push(redact_header(header));
+            }
+        }
+    }
+
+    redacted
+}
+
+// Redact the value of a `Name:Value` header, if the header is a secret.
+fn redact_header(header: &str) -> String {
+    match header.split_once(':') {
+        Some((name, _)) if SECRET_HEADERS.contains(&name.trim().to_lowercase().as_str()) => {
+            format!("{name}:***")
+        }
+        _ => header.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use color_eyre::eyre::eyre;
+    use tokio::sync::oneshot;
+
+    use super::{
+        downloader::{FlakyDownloader, ScriptedDownloader},
+        line_severity, parse_downloader_retry, parse_geo_block_reason, parse_output_file,
+        parse_percent_done, parse_resumed_at_byte, parse_skip_reason, LineSeverity, ProgressDetail,
+        Video,
+    };
+    use crate::{
+        session::{Config, Event, ShutdownSignal, VideoStage},
+        state::State,
+    };
+
+    #[test]
+    fn parses_percent_done() {
+        assert_eq!(
+            parse_percent_done("[download]   0.0% of   56.43MiB at  Unknown B/s ETA Unknown"),
+            Some(0.0)
+        );
+        assert_eq!(
+            parse_percent_done("[download]   5% of 56.43MiB at 94.93KiB/s ETA 10:09"),
+            Some(5.0)
+        );
+        assert_eq!(
+            parse_percent_done("[download] 100.0% of   56.43MiB in 00:00:42 at 1.33MiB/s"),
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn completion_lines_are_100_percent_done() {
+        assert_eq!(
+            parse_percent_done("[download] My Video [12345].mp4 has already been downloaded"),
+            Some(100.0)
+        );
+        assert_eq!(
+            parse_percent_done(r#"[Merger] Merging formats into "My Video [12345].mp4""#),
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn detects_resumed_downloads() {
+        assert_eq!(
+            parse_resumed_at_byte("[download] Resuming download at byte 1234567"),
+            Some(1_234_567)
+        );
+        assert_eq!(
+            parse_resumed_at_byte("[download]  12.3% of 56.43MiB at 1.33MiB/s ETA 00:42"),
+            None
+        );
+        assert_eq!(parse_resumed_at_byte("[download] Unable to resume"), None);
+    }
+
+    #[test]
+    fn detects_files_exceeding_max_filesize() {
+        assert_eq!(
+            parse_skip_reason("[download] File is larger than max-filesize (58998208 bytes > 10485760 bytes). Aborting."),
+            Some("File is larger than max-filesize (58998208 bytes > 10485760 bytes)")
+        );
+        assert_eq!(
+            parse_skip_reason("[download] Destination: My Video [12345].mp4"),
+            None
+        );
+    }
+
+    #[test]
+    fn detects_geo_blocked_videos() {
+        assert_eq!(
+            parse_geo_block_reason(
+                "ERROR: [vimeo] 12345: This video is not available in your country."
+            ),
+            Some("[vimeo] 12345: This video is not available in your country.")
+        );
+        assert_eq!(
+            parse_geo_block_reason("ERROR: [youtube] abc: The uploader has not made this video available in your country"),
+            Some("[youtube] abc: The uploader has not made this video available in your country")
+        );
+        assert_eq!(
+            parse_geo_block_reason("ERROR: [generic] This video is Geo-Restricted"),
+            Some("[generic] This video is Geo-Restricted")
+        );
+        assert_eq!(
+            parse_geo_block_reason("ERROR: [vimeo] 12345: HTTP Error 404: Not Found"),
+            None
+        );
+    }
+
+    #[test]
+    fn detects_downloader_retries() {
+        assert_eq!(
+            parse_downloader_retry("[download] Got error: HTTP Error 503: Service Unavailable. Retrying fragment 12 (1/10)...").as_deref(),
+            Some("1/10")
+        );
+        assert_eq!(
+            parse_downloader_retry(
+                "[download] Got error: The read operation timed out. Retrying (2/inf)..."
+            )
+            .as_deref(),
+            Some("2/inf")
+        );
+        assert_eq!(
+            parse_downloader_retry(
+                "[download] Got server HTTP error: Connection reset. Retrying (attempt 3 of 10)..."
+            )
+            .as_deref(),
+            Some("3/10")
+        );
+        assert_eq!(
+            parse_downloader_retry("[download]  12.3% of 56.43MiB at 94.93KiB/s ETA 10:09"),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_lines_without_percent_done() {
+        assert_eq!(
+            parse_percent_done("[download] Destination: My Video [12345].f137.mp4"),
+            None
+        );
+        assert_eq!(parse_percent_done("[download] +5% of nothing"), None);
+    }
+
+    #[test]
+    fn captures_output_files_in_subdirectories() {
+        assert_eq!(
+            parse_output_file("[download] Destination: Uploader/Sub dir/Title [123].f1.mp4"),
+            Some("Uploader/Sub dir/Title [123].f1.mp4")
+        );
+        assert_eq!(
+            parse_output_file(
+                "[download] Uploader/Sub dir/Title [123].mp4 has already been downloaded"
+            ),
+            Some("Uploader/Sub dir/Title [123].mp4")
+        );
+        assert_eq!(
+            parse_output_file(
+                r#"[Merger] Merging formats into "Uploader/Sub dir/Title [123].mp4""#
+            ),
+            Some("Uploader/Sub dir/Title [123].mp4")
+        );
+    }
+
+    #[test]
+    fn captures_windows_and_trimmed_output_files() {
+        // With `--windows-filenames`, reserved characters are replaced by their full width variants.
+        assert_eq!(
+            parse_output_file("[download] Destination: Talk： Part 1 ｜ Q＆A [123].f1.mp4"),
+            Some("Talk： Part 1 ｜ Q＆A [123].f1.mp4")
+        );
+        assert_eq!(
+            parse_output_file(r"[download] Destination: Uploader\Talk： Part 1 [123].mp4"),
+            Some(r"Uploader\Talk： Part 1 [123].mp4")
+        );
+        // With `--trim-filenames`, the extension is kept.
+        assert_eq!(
+            parse_output_file(r#"[Merger] Merging formats into "A very long tit.mp4""#),
+            Some("A very long tit.mp4")
+        );
+    }
+
+    #[test]
+    fn redacts_secrets_from_downloader_options() {
+        let options = [
+            "--username",
+            "user",
+            "--password",
+            "secret",
+            "--add-header",
+            "Cookie: session=secret",
+            "--add-header",
+            "Accept-Language:en",
+            "--format",
+            "bv+ba",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            super::redact_downloader_options(&options),
+            [
+                "--username",
+                "user",
+                "--password",
+                "***",
+                "--add-header",
+                "Cookie:***",
+                "--add-header",
+                "Accept-Language:en",
+                "--format",
+                "bv+ba",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn scripted_download_finishes() {
+        let state = Arc::new(State::new(Config::default()).with_downloader_command(
+            ScriptedDownloader {
+                lines: vec![
+                    "[download] Destination: My Video [12345].mp4",
+                    "[download]  50.0% of 1.00MiB at 1.00MiB/s ETA 00:01",
+                    "[download] 100% of 1.00MiB in 00:00:01 at 1.00MiB/s",
+                ],
+                exit_code: Some(0),
+            },
+        ));
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        video.clone().download(state).await.unwrap();
+
+        let video = video.read().await;
+        assert!(matches!(video.stage(), super::Stage::Finished));
+        assert_eq!(
+            video.output_file().map(String::as_str),
+            Some("My Video [12345].mp4")
+        );
+        assert_eq!(video.percent_done(), Some(100.0));
+    }
+
+    #[tokio::test]
+    async fn scripted_lines_are_printed_verbatim() {
+        let state = Arc::new(State::new(Config::default()).with_downloader_command(
+            ScriptedDownloader {
+                lines: vec![r"[download] Destination: Don't \n Stop [12345].mp4"],
+                exit_code: Some(0),
+            },
+        ));
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        video.clone().download(state).await.unwrap();
+
+        assert_eq!(
+            video.read().await.output_file().map(String::as_str),
+            Some(r"Don't \n Stop [12345].mp4")
+        );
+    }
+
+    #[test]
+    fn classifies_line_severity() {
+        assert_eq!(
+            line_severity("ERROR: [vimeo] 12345: Unable to download JSON metadata"),
+            LineSeverity::Error
+        );
+        assert_eq!(
+            line_severity("WARNING: [vimeo] Failed to parse XML"),
+            LineSeverity::Warning
+        );
+        assert_eq!(
+            line_severity("[download] Destination: ERROR: Report.mp4"),
+            LineSeverity::Info
+        );
+    }
+
+    #[tokio::test]
+    async fn error_lines_do_not_fail_successful_download() {
+        let state = Arc::new(State::new(Config::default()).with_downloader_command(
+            ScriptedDownloader {
+                lines: vec![
+                    "WARNING: [vimeo] 12345: Failed to parse XML",
+                    "ERROR: [vimeo] 12345: hls-1080p: Unable to download format, trying next format",
+                    "[download] Destination: My Video [12345].mp4",
+                    "[download] 100% of 1.00MiB in 00:00:01 at 1.00MiB/s",
+                ],
+                exit_code: Some(0),
+            },
+        ));
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        video.clone().download(state).await.unwrap();
+
+        assert!(matches!(video.read().await.stage(), super::Stage::Finished));
+    }
+
+    #[tokio::test]
+    async fn failed_scripted_download_is_retried() {
+        let state = Arc::new(
+            State::new(Config {
+                download_attempts: 2,
+                ..Config::default()
+            })
+            .with_downloader_command(ScriptedDownloader {
+                lines: vec!["ERROR: Unable to download webpage: HTTP Error 500"],
+                exit_code: Some(1),
+            }),
+        );
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        video.clone().download(state).await.unwrap();
+
+        let video = video.read().await;
+        assert!(matches!(video.stage(), super::Stage::Failed));
+        assert_eq!(video.attempt(), 2);
+    }
+
+    #[tokio::test]
+    async fn retried_download_starts_afresh() {
+        let state = Arc::new(
+            State::new(Config {
+                download_attempts: 2,
+                ..Config::default()
+            })
+            .with_downloader_command(FlakyDownloader::new(
+                ScriptedDownloader {
+                    lines: vec![
+                        "[download]  50.0% of 1.00MiB at 1.00MiB/s ETA 00:01",
+                        "ERROR: Unable to download webpage: HTTP Error 500",
+                    ],
+                    exit_code: Some(1),
+                },
+                ScriptedDownloader {
+                    lines: vec!["[download] Destination: My Video [12345].mp4"],
+                    exit_code: Some(0),
+                },
+            )),
+        );
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        video.clone().download(state).await.unwrap();
+
+        let video = video.read().await;
+        assert!(matches!(video.stage(), super::Stage::Finished));
+        assert_eq!(video.attempt(), 2);
+        assert_eq!(video.percent_done(), None);
+    }
+
+    #[tokio::test]
+    async fn shutdown_interrupts_scripted_download_without_retry() {
+        let state = Arc::new(
+            State::new(Config {
+                download_attempts: 2,
+                ..Config::default()
+            })
+            .with_downloader_command(ScriptedDownloader {
+                lines: vec!["[download] Destination: My Video [12345].mp4"],
+                exit_code: None,
+            }),
+        );
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        let download = tokio::spawn(video.clone().download(state));
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while video.read().await.stage().process_id().is_none() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the download should start");
+        video
+            .initiate_shutdown(ShutdownSignal::Sigint)
+            .await
+            .unwrap();
+        download.await.unwrap().unwrap();
+
+        let video = video.read().await;
+        assert!(matches!(video.stage(), super::Stage::Failed));
+        assert_eq!(video.attempt(), 1);
+    }
+
+    #[tokio::test]
+    async fn downloads_are_queued_only_if_limited() {
+        let state = Arc::new(State::new(Config::default()).with_downloader_command(
+            ScriptedDownloader {
+                lines: vec![],
+                exit_code: Some(0),
+            },
+        ));
+        let mut events = state.subscribe();
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+        state.push_video(video.clone()).await;
+
+        video.clone().download(state).await.unwrap();
+
+        while let Ok(event) = events.try_recv() {
+            if let Event::VideoStage { stage, .. } = event {
+                assert_ne!(stage, VideoStage::Queued);
+            }
+        }
+        assert!(matches!(video.read().await.stage(), super::Stage::Finished));
+    }
+
+    #[tokio::test]
+    async fn shutdown_cancels_queued_downloads() {
+        let state = Arc::new(
+            State::new(Config {
+                max_concurrent_downloads: Some(1),
+                ..Config::default()
+            })
+            .with_downloader_command(ScriptedDownloader {
+                lines: vec!["[download] Destination: My Video [12345].mp4"],
+                exit_code: None,
+            }),
+        );
+        let running = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+        let queued = Arc::new(Video::new(
+            "https://player.vimeo.com/video/67890",
+            None::<String>,
+        ));
+        state.push_video(running.clone()).await;
+        state.push_video(queued.clone()).await;
+
+        let downloads = [
+            tokio::spawn(running.clone().download(state.clone())),
+            tokio::spawn(queued.clone().download(state.clone())),
+        ];
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while running.read().await.stage().process_id().is_none()
+                || !matches!(queued.read().await.stage(), super::Stage::Queued)
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("one download should run while the other is queued");
+
+        let (shutdown_complete, _) = oneshot::channel();
+        state.initiate_shutdown(shutdown_complete).await.ok();
+        for download in downloads {
+            download.await.unwrap().unwrap();
+        }
+        state.set_stage_done().await;
+
+        assert!(matches!(
+            queued.read().await.stage(),
+            super::Stage::Cancelled
+        ));
+        assert!(state.is_done().await);
+    }
+
+    #[tokio::test]
+    async fn failing_title_fetch_does_not_fail_the_download() {
+        let state = Arc::new(State::new(Config {
+            extract_only: true,
+            ..Config::default()
+        }));
+        let video = Arc::new(
+            Video::from_url("https://player.vimeo.com/video/12345")
+                .referer(Some("https://example.com/"))
+                .title(Some(String::from("Placeholder")))
+                .fetch_title(|_| Box::pin(async { Err(eyre!("title page unavailable")) }))
+                .build(),
+        );
+
+        video.clone().download(state).await.unwrap();
+
+        assert_eq!(video.referer(), Some("https://example.com/"));
+        assert_eq!(video.title().await.as_deref(), Some("Placeholder"));
+    }
+
+    #[test]
+    fn displays_the_spawned_command_with_secrets_redacted() {
+        let state = State::new(Config {
+            downloader: String::from("yt-dlp"),
+            video_password: Some(String::from("secret")),
+            headers: vec![(String::from("Cookie"), String::from("session=secret"))],
+            output_template: Some(String::from("%(title)s.%(ext)s")),
+            ..Config::default()
+        });
+        let video = Video::new(
+            "https://player.vimeo.com/video/12345?h=abc&app_id=1",
+            Some("https://example.com/"),
+        );
+
+        let command =
+            video.download_command(&state, &[String::from("--format"), String::from("bv+ba")]);
+
+        assert_eq!(
+            super::display_command(&command),
+            "yt-dlp --add-header Referer:https://example.com/ --add-header 'Cookie:***' \
+             --video-password '***' --newline --no-colors --legacy-server-connect --continue \
+             -o '%(title)s.%(ext)s' --format bv+ba \
+             'https://player.vimeo.com/video/12345?h=abc&app_id=1'"
+        );
+    }
+
+    #[tokio::test]
+    async fn stage_changes_are_published() {
+        let state = State::new(Config::default());
+        let mut events = state.subscribe();
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+        state.push_video(video.clone()).await;
+
+        video.set_stage_queued().await;
+
+        loop {
+            if let Event::VideoStage { url, stage } = events.recv().await.unwrap() {
+                assert_eq!(url, "https://player.vimeo.com/video/12345");
+                assert_eq!(stage, VideoStage::Queued);
+                assert_eq!(stage.to_string(), "queued");
+                break;
             }
-            None => None,
         }
     }
 
-    pub(crate) fn output_file(&self) -> &Option<String> {
-        &self.output_file
+    #[tokio::test]
+    async fn subtitle_progress_does_not_affect_video_progress() {
+        let state = State::new(Config::default());
+        let video = Video::new("https://player.vimeo.com/video/12345", None::<String>);
+
+        for line in [
+            "[info] Writing video subtitles to: My Video [12345].en.vtt",
+            "[download] Destination: My Video [12345].en.vtt",
+            "[download] 100% of 1.20KiB in 00:00:00 at 9.41KiB/s",
+            "[download] Destination: My Video [12345].mp4",
+            "[download]   5% of 56.43MiB at 94.93KiB/s ETA 10:09",
+        ] {
+            video.update_line(&state, line.to_owned()).await;
+        }
+
+        assert_eq!(
+            video.output_file().await.as_deref(),
+            Some("My Video [12345].mp4")
+        );
+        assert_eq!(video.read().await.percent_done(), Some(5.0));
+        assert_eq!(
+            video.read().await.subtitle_files(),
+            ["My Video [12345].en.vtt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8_output_does_not_fail_the_stream() {
+        let state = Arc::new(State::new(Config::default()));
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+
+        let output: &'static [u8] =
+            b"[download] Destination: Caf\xe9 [12345].mp4\r\n[download]   5% of 56.43MiB at 94.93KiB/s ETA 10:09\n";
+        video
+            .clone()
+            .consume_stream(state, output)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            video.output_file().await.as_deref(),
+            Some("Caf\u{FFFD} [12345].mp4")
+        );
+        assert_eq!(video.read().await.percent_done(), Some(5.0));
+    }
+
+    #[tokio::test]
+    async fn merging_is_detected_until_the_download_restarts() {
+        let state = State::new(Config::default());
+        let video = Video::new("https://player.vimeo.com/video/12345", None::<String>);
+
+        video
+            .update_line(
+                &state,
+                String::from(r#"[Merger] Merging formats into "My Video [12345].mp4""#),
+            )
+            .await;
+        assert!(video.fields.read().await.merging);
+
+        let (_signal_shutdown, shutdown_signal) = oneshot::channel();
+        video.set_stage_running(1, shutdown_signal).await;
+        assert!(!video.fields.read().await.merging);
+    }
+
+    #[test]
+    fn substitutes_url_placeholder_in_downloader_options() {
+        let options = [
+            "--exec",
+            "echo {url} >> done.txt",
+            "--format",
+            "{{url}} {url}",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            super::substitute_url(&options, "https://vimeo.com/123"),
+            [
+                "--exec",
+                "echo 'https://vimeo.com/123' >> done.txt",
+                "--format",
+                "{url} https://vimeo.com/123",
+            ]
+        );
+    }
+
+    #[test]
+    fn url_is_shell_quoted_only_in_shell_commands() {
+        let options = [
+            "--output",
+            "{url}.%(ext)s",
+            "--add-header",
+            "X-Source: {url}",
+            "--exec-before-download",
+            "echo {url}",
+            "--exec=echo {url}",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            super::substitute_url(&options, "https://vimeo.com/123"),
+            [
+                "--output",
+                "https://vimeo.com/123.%(ext)s",
+                "--add-header",
+                "X-Source: https://vimeo.com/123",
+                "--exec-before-download",
+                "echo 'https://vimeo.com/123'",
+                "--exec=echo 'https://vimeo.com/123'",
+            ]
+        );
+    }
+
+    #[test]
+    fn substituted_url_is_shell_quoted() {
+        let options = ["--exec", "echo {url}"].map(String::from);
+        let url = "https://example.com/video?a=1;touch pwned|cat $(id) `id` 'q'";
+
+        let substituted = super::substitute_url(&options, url);
+        assert_eq!(
+            substituted[1],
+            r"echo 'https://example.com/video?a=1;touch pwned|cat $(id) `id` '\''q'\'''"
+        );
+
+        // The shell receives the URL as a single, literal argument.
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&substituted[1])
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{url}\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn throttled_progress_lines_are_coalesced_into_the_latest() {
+        let state = State::new(Config {
+            line_throttle: Some(Duration::from_secs(100)),
+            ..Config::default()
+        });
+        let video = Video::new("https://player.vimeo.com/video/12345", None::<String>);
+
+        for line in [
+            "[download] Destination: My Video [12345].f1.mp4",
+            "[download]  10.0% of   52.10MiB at    1.30MiB/s ETA 00:36",
+            "[download]  20.0% of   52.10MiB at    1.30MiB/s ETA 00:32",
+            "[download]  30.0% of   52.10MiB at    1.30MiB/s ETA 00:28",
+        ] {
+            video.update_line(&state, line.to_owned()).await;
+        }
+        assert_eq!(video.read().await.percent_done(), Some(10.0));
+
+        // The held back line is applied before any other line.
+        video
+            .update_line(
+                &state,
+                String::from("[download] Destination: My Video [12345].f2.m4a"),
+            )
+            .await;
+        assert_eq!(video.read().await.percent_done(), Some(30.0));
+        assert_eq!(
+            video.output_file().await.as_deref(),
+            Some("My Video [12345].f2.m4a")
+        );
+
+        // The held back line is applied once the output ends.
+        video
+            .update_line(
+                &state,
+                String::from("[download]  50.0% of    4.33MiB at    2.16MiB/s ETA 00:01"),
+            )
+            .await;
+        assert_eq!(video.read().await.percent_done(), Some(30.0));
+        video.flush_line(&state).await;
+        assert_eq!(video.read().await.percent_done(), Some(50.0));
+    }
+
+    // Variant of the `ProgressDetail` of a video's latest line.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Detail {
+        Raw,
+        Parsed,
+        Streaming,
+    }
+
+    // Downloader output transcript, and the video state expected after replaying it through `Video::update_line`.
+    struct Transcript {
+        name: &'static str,
+        lines: &'static [&'static str],
+        output_file: Option<&'static str>,
+        percent_done: Option<f64>,
+        detail: Detail,
     }
 
-    pub(crate) fn percent_done(&self) -> &Option<f64> {
-        &self.percent_done
+    // Real `yt-dlp` output, covering the kinds of lines the progress regexes depend on.
+    const TRANSCRIPTS: &[Transcript] = &[
+        Transcript {
+            name: "download in progress",
+            lines: &[
+                "[vimeo] 12345: Downloading webpage",
+                "[info] 12345: Downloading 1 format(s): http-1080p",
+                "[download] Destination: My Video [12345].mp4",
+                "[download]   0.0% of   56.43MiB at  Unknown B/s ETA Unknown",
+                "[download]  42.1% of   56.43MiB at    1.33MiB/s ETA 00:24",
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(42.1),
+            detail: Detail::Parsed,
+        },
+        Transcript {
+            name: "download completed",
+            lines: &[
+                "[download] Destination: My Video [12345].mp4",
+                "[download]  99.9% of   56.43MiB at    1.33MiB/s ETA 00:00",
+                "[download] 100% of   56.43MiB in 00:00:42 at 1.33MiB/s",
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(100.0),
+            detail: Detail::Parsed,
+        },
+        Transcript {
+            name: "merging formats",
+            lines: &[
+                "[info] 12345: Downloading 1 format(s): hls-1080p+hls-audio-high",
+                "[download] Destination: My Video [12345].f1.mp4",
+                "[download] 100% of   52.10MiB in 00:00:40 at 1.30MiB/s",
+                "[download] Destination: My Video [12345].f2.m4a",
+                "[download] 100% of    4.33MiB in 00:00:02 at 2.16MiB/s",
+                r#"[Merger] Merging formats into "My Video [12345].mp4""#,
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(100.0),
+            detail: Detail::Raw,
+        },
+        Transcript {
+            name: "embedding metadata and thumbnail",
+            lines: &[
+                "[info] Downloading video thumbnail 0 ...",
+                "[info] Writing video thumbnail 0 to: My Video [12345].jpg",
+                "[download] Destination: My Video [12345].f1.mp4",
+                "[download] 100% of   52.10MiB in 00:00:40 at 1.30MiB/s",
+                "[download] Destination: My Video [12345].f2.m4a",
+                "[download] 100% of    4.33MiB in 00:00:02 at 2.16MiB/s",
+                r#"[Merger] Merging formats into "My Video [12345].mp4""#,
+                r#"[Metadata] Adding metadata to "My Video [12345].mp4""#,
+                r#"[EmbedThumbnail] ffmpeg: Adding thumbnail to "My Video [12345].mp4""#,
+                "Deleting original file My Video [12345].jpg (pass -k to keep)",
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(100.0),
+            detail: Detail::Raw,
+        },
+        Transcript {
+            name: "extracting audio",
+            lines: &[
+                "[download] Destination: My Video [12345].m4a",
+                "[download] 100% of    4.33MiB in 00:00:02 at 2.16MiB/s",
+                "[ExtractAudio] Destination: My Video [12345].mp3",
+            ],
+            output_file: Some("My Video [12345].mp3"),
+            percent_done: Some(100.0),
+            detail: Detail::Raw,
+        },
+        Transcript {
+            name: "HLS fragments of estimated size",
+            lines: &[
+                "[hlsnative] Downloading m3u8 manifest",
+                "[hlsnative] Total fragments: 340",
+                "[download] Destination: My Video [12345].mp4",
+                "[download]   5.0% of ~  1.23GiB at    2.34MiB/s ETA 05:01 (frag 17/340)",
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(5.0),
+            detail: Detail::Parsed,
+        },
+        Transcript {
+            name: "HLS livestream of unknown size",
+            lines: &[
+                "[hlsnative] Downloading m3u8 manifest",
+                "[download] Destination: My Event [12345].mp4",
+                "[download]  123.45MiB at    2.00MiB/s (00:01:02) (frag 17/340)",
+            ],
+            output_file: Some("My Event [12345].mp4"),
+            percent_done: None,
+            detail: Detail::Streaming,
+        },
+        Transcript {
+            name: "already downloaded",
+            lines: &[
+                "[vimeo] 12345: Downloading webpage",
+                "[download] My Video [12345].mp4 has already been downloaded",
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(100.0),
+            detail: Detail::Raw,
+        },
+        Transcript {
+            name: "error after partial download",
+            lines: &[
+                "[download] Destination: My Video [12345].mp4",
+                "[download]  12.5% of   56.43MiB at    1.33MiB/s ETA 00:37",
+                "ERROR: [vimeo] 12345: HTTP Error 404: Not Found",
+            ],
+            output_file: Some("My Video [12345].mp4"),
+            percent_done: Some(12.5),
+            detail: Detail::Raw,
+        },
+        Transcript {
+            name: "error before download",
+            lines: &[
+                "[vimeo] 12345: Downloading webpage",
+                "ERROR: [vimeo] 12345: Unable to download webpage: HTTP Error 403: Forbidden",
+            ],
+            output_file: None,
+            percent_done: None,
+            detail: Detail::Raw,
+        },
+    ];
+
+    #[tokio::test]
+    async fn replays_downloader_output_transcripts() {
+        for transcript in TRANSCRIPTS {
+            let state = State::new(Config::default());
+            let video = Video::new("https://player.vimeo.com/video/12345", None::<String>);
+            for line in transcript.lines {
+                video.update_line(&state, (*line).to_owned()).await;
+            }
+
+            let video_read = video.read().await;
+            let name = transcript.name;
+            assert_eq!(
+                video_read.output_file().map(String::as_str),
+                transcript.output_file,
+                "{name}"
+            );
+            assert_eq!(video_read.percent_done(), transcript.percent_done, "{name}");
+
+            let detail = match video_read.progress_detail() {
+                Some(ProgressDetail::Raw(_)) => Some(Detail::Raw),
+                Some(progress_detail) if progress_detail.is_streaming() => Some(Detail::Streaming),
+                Some(ProgressDetail::Parsed { .. }) => Some(Detail::Parsed),
+                None => None,
+            };
+            assert_eq!(detail, Some(transcript.detail), "{name}");
+        }
     }
 }
@@ -0,0 +1,87 @@
+use std::fmt::Debug;
+#[cfg(test)]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio::process::Command;
+
+// Creates the command running the downloader, before any arguments are added.
+// Tests replace the downloader by a fake one, printing scripted output lines.
+pub(crate) trait DownloaderCommand: Debug + Send + Sync {
+    fn command(&self, downloader: &str) -> Command;
+}
+
+// Runs the configured downloader, such as `yt-dlp`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SystemDownloader;
+
+impl DownloaderCommand for SystemDownloader {
+    fn command(&self, downloader: &str) -> Command {
+        Command::new(downloader)
+    }
+}
+
+// Prints the given lines to stdout, then exits with the given exit code, ignoring all arguments.
+// Sleeps until interrupted if `exit_code` is `None`, exiting on SIGINT or SIGTERM like the downloader,
+// which prints a last line before exiting.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct ScriptedDownloader {
+    pub(crate) lines: Vec<&'static str>,
+    pub(crate) exit_code: Option<u8>,
+}
+
+#[cfg(test)]
+impl DownloaderCommand for ScriptedDownloader {
+    fn command(&self, _downloader: &str) -> Command {
+        let mut statements: Vec<String> = self
+            .lines
+            .iter()
+            // Unlike `echo`, `printf '%s'` prints backslashes verbatim in every shell.
+            .map(|line| format!("printf '%s\\n' {}", super::shell_quote(line)))
+            .collect();
+        match self.exit_code {
+            Some(exit_code) => statements.push(format!("exit {exit_code}")),
+            None => statements.extend([
+                String::from("trap 'echo \"ERROR: Interrupted by user\"; exit 1' INT TERM"),
+                String::from("while :; do sleep 0.05; done"),
+            ]),
+        }
+        let script = statements.join("\n");
+
+        let mut command = Command::new("sh");
+        // Further arguments are passed as positional parameters, which the script ignores.
+        command.arg("-c").arg(script).arg("downloader");
+        command
+    }
+}
+
+// Runs the failing downloader on the first attempt, and the succeeding one on all further attempts.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct FlakyDownloader {
+    failing: ScriptedDownloader,
+    succeeding: ScriptedDownloader,
+    failed: AtomicBool,
+}
+
+#[cfg(test)]
+impl FlakyDownloader {
+    pub(crate) fn new(failing: ScriptedDownloader, succeeding: ScriptedDownloader) -> Self {
+        Self {
+            failing,
+            succeeding,
+            failed: AtomicBool::new(false),
+        }
+    }
+}
+
+#[cfg(test)]
+impl DownloaderCommand for FlakyDownloader {
+    fn command(&self, downloader: &str) -> Command {
+        if self.failed.swap(true, Ordering::Relaxed) {
+            self.succeeding.command(downloader)
+        } else {
+            self.failing.command(downloader)
+        }
+    }
+}
@@ -1,5 +1,21 @@
 use std::{borrow::Cow, fmt::Display, ops::Range};
 
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// Matches progress lines of both `yt-dlp` and `youtube-dl`, e.g.:
+// - `[download]  12.3% of ~  1.23GiB at    2.34MiB/s ETA 05:01 (frag 5/100)` (`yt-dlp`)
+// - `[download]  10.0% of 12.34MiB at Unknown speed ETA Unknown ETA` (`youtube-dl`)
+static REGEX_DOWNLOAD_PROGRESS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[download\]\s+(?P<percent>\d+(?:\.\d+)?)% of\s+(?P<size>(?:~\s*)?\d+(?:\.\d+)?(?:[KMGT]i)?B)(?: at\s+(?P<speed>(?:~\s*)?\d+(?:\.\d+)?(?:[KMGT]i)?B/s|Unknown (?:B/s|speed)))?(?: ETA\s+(?P<eta>[\d:-]+|Unknown(?: ETA)?))?(?: \(frag (?P<frag>\d+)/(?P<frag_total>\d+)\))?").unwrap()
+});
+
+// Matches progress lines of fragment-based downloads with unknown total size, such as livestreams, e.g.:
+// - `[download]  123.45MiB at    2.00MiB/s (00:01:02) (frag 12/340)` (`yt-dlp`)
+static REGEX_DOWNLOAD_PROGRESS_UNKNOWN_SIZE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[download\]\s+(?:~\s*)?\d+(?:\.\d+)?(?:[KMGT]i)?B at\s+(?P<speed>(?:~\s*)?\d+(?:\.\d+)?(?:[KMGT]i)?B/s|Unknown B/s)(?: \([\d:]+\))?(?: \(frag (?P<frag>\d+)/(?P<frag_total>\d+)\))").unwrap()
+});
+
 pub(crate) enum ProgressDetail<'a> {
     Raw(&'a str),
     Parsed {
@@ -15,7 +31,142 @@ pub(crate) enum ProgressDetail<'a> {
     },
 }
 
+// Download progress lines, which may be coalesced, unlike lines announcing files or errors.
+// Cheaper than `ProgressDetail::parse`, as nothing is captured.
+pub(crate) fn is_progress_line(line: &str) -> bool {
+    REGEX_DOWNLOAD_PROGRESS.is_match(line) || REGEX_DOWNLOAD_PROGRESS_UNKNOWN_SIZE.is_match(line)
+}
+
 impl<'a> ProgressDetail<'a> {
+    // Parse a downloader output line, falling back to the last known percentage
+    // if the line does not provide a fresh value.
+    pub(crate) fn parse(line: &'a str, last_percent: Option<f64>) -> Self {
+        let maybe_captures = REGEX_DOWNLOAD_PROGRESS
+            .captures(line)
+            .or_else(|| REGEX_DOWNLOAD_PROGRESS_UNKNOWN_SIZE.captures(line));
+
+        match maybe_captures {
+            Some(captures) => {
+                let percent = captures
+                    .name("percent")
+                    .and_then(|percent_match| percent_match.as_str().parse::<f64>().ok())
+                    .or(last_percent);
+
+                let size = captures.name("size").map(|size_match| size_match.range());
+                let speed = captures
+                    .name("speed")
+                    .map(|speed_match| speed_match.range());
+                let eta = captures.name("eta").map(|eta_match| eta_match.range());
+
+                let frag = captures
+                    .name("frag")
+                    .and_then(|frag_match| frag_match.as_str().parse::<u16>().ok());
+
+                let frag_total = captures
+                    .name("frag_total")
+                    .and_then(|frag_total_match| frag_total_match.as_str().parse::<u16>().ok());
+
+                Self::Parsed {
+                    line,
+                    percent,
+                    size,
+                    speed,
+                    eta,
+                    frag,
+                    frag_total,
+                }
+            }
+            None => Self::Raw(line),
+        }
+    }
+
+    // Fragment-based downloads of unknown total size, such as livestreams.
+    pub(crate) fn is_streaming(&self) -> bool {
+        matches!(
+            self,
+            Self::Parsed {
+                size: None,
+                frag: Some(_),
+                ..
+            }
+        )
+    }
+
+    // Percentage of downloaded fragments, if the byte percentage is not meaningful,
+    // as the total size is unknown or only estimated.
+    pub(crate) fn fragment_percent(&self) -> Option<f64> {
+        match self {
+            Self::Parsed {
+                line,
+                size,
+                frag: Some(frag),
+                frag_total: Some(frag_total),
+                ..
+            } if *frag_total > 0
+                && size
+                    .as_ref()
+                    .is_none_or(|size| line[size.clone()].starts_with('~')) =>
+            {
+                Some(f64::from(*frag) / f64::from(*frag_total) * 100.0)
+            }
+            _ => None,
+        }
+    }
+
+    // Downloaded and total number of fragments of fragment-based downloads, such as HLS.
+    pub(crate) fn fragments(&self) -> Option<(u16, u16)> {
+        match self {
+            Self::Parsed {
+                frag: Some(frag),
+                frag_total: Some(frag_total),
+                ..
+            } if *frag_total > 0 => Some((*frag, *frag_total)),
+            _ => None,
+        }
+    }
+
+    // Download speed in bytes per second, unless unknown.
+    pub(crate) fn parsed_speed_bytes_per_sec(&self) -> Option<f64> {
+        let Self::Parsed {
+            line,
+            speed: Some(speed),
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        // E.g. `2.34MiB/s`, or `~ 2.34MiB/s` if estimated.
+        parse_bytes(line[speed.clone()].strip_suffix("/s")?)
+    }
+
+    // Total size in bytes, estimated for some fragment-based downloads, unless unknown.
+    pub(crate) fn parsed_size_bytes(&self) -> Option<f64> {
+        let Self::Parsed {
+            line,
+            size: Some(size),
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        parse_bytes(&line[size.clone()])
+    }
+
+    // Total size reported by a completion line, such as `[download] 100% of 12.34MiB in 00:00:05`.
+    pub(crate) fn completed_size(&self) -> Option<&'a str> {
+        match self {
+            Self::Parsed {
+                line,
+                percent: Some(percent),
+                size: Some(size),
+                ..
+            } if *percent >= 100.0 => Some(&line[size.clone()]),
+            _ => None,
+        }
+    }
+
     pub(crate) fn to_table_cells(&self) -> Option<[Cow<'a, str>; 4]> {
         match self {
             Self::Raw(_) => None,
@@ -56,7 +207,31 @@ impl<'a> ProgressDetail<'a> {
     }
 }
 
-impl<'a> Display for ProgressDetail<'a> {
+// Parse a size in the downloader's binary units, e.g. `12.34MiB`, or `~ 12.34MiB` if estimated.
+pub(crate) fn parse_bytes(size: &str) -> Option<f64> {
+    let size = size
+        .trim_start_matches('~')
+        .trim_start()
+        .strip_suffix('B')?;
+    let (number, multiplier) = match size.strip_suffix('i') {
+        Some(size) => {
+            let (number, unit) = size.split_at(size.len().checked_sub(1)?);
+            let multiplier = match unit {
+                "K" => 1024_f64,
+                "M" => 1024_f64.powi(2),
+                "G" => 1024_f64.powi(3),
+                "T" => 1024_f64.powi(4),
+                _ => return None,
+            };
+            (number, multiplier)
+        }
+        None => (size, 1.0),
+    };
+
+    number.parse::<f64>().ok().map(|number| number * multiplier)
+}
+
+impl Display for ProgressDetail<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Parsed {
@@ -94,3 +269,189 @@ impl<'a> Display for ProgressDetail<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::ProgressDetail;
+
+    // Parse a line into its table cells "Size", "Speed", "ETA" and "Fragments", plus the percentage.
+    fn parse(line: &str) -> (Option<f64>, [String; 4]) {
+        let progress = ProgressDetail::parse(line, None);
+        let ProgressDetail::Parsed { percent, .. } = progress else {
+            panic!("line was not parsed: '{line}'");
+        };
+
+        (
+            percent,
+            progress.to_table_cells().unwrap().map(Cow::into_owned),
+        )
+    }
+
+    #[test]
+    fn parses_yt_dlp_progress() {
+        assert_eq!(
+            parse("[download]  12.3% of ~  1.23GiB at    2.34MiB/s ETA 05:01 (frag 5/100)"),
+            (
+                Some(12.3),
+                [
+                    "~  1.23GiB".into(),
+                    "2.34MiB/s".into(),
+                    "05:01".into(),
+                    "5 / 100".into()
+                ]
+            )
+        );
+        assert_eq!(
+            parse("[download]   0.0% of   56.43MiB at  Unknown B/s ETA Unknown"),
+            (
+                Some(0.0),
+                [
+                    "56.43MiB".into(),
+                    "Unknown B/s".into(),
+                    "Unknown".into(),
+                    String::new()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn parses_youtube_dl_progress() {
+        assert_eq!(
+            parse("[download]   0.4% of 56.43MiB at 94.93KiB/s ETA 10:09"),
+            (
+                Some(0.4),
+                [
+                    "56.43MiB".into(),
+                    "94.93KiB/s".into(),
+                    "10:09".into(),
+                    String::new()
+                ]
+            )
+        );
+        assert_eq!(
+            parse("[download]  10.0% of 12.34MiB at Unknown speed ETA Unknown ETA"),
+            (
+                Some(10.0),
+                [
+                    "12.34MiB".into(),
+                    "Unknown speed".into(),
+                    "Unknown ETA".into(),
+                    String::new()
+                ]
+            )
+        );
+        assert_eq!(
+            parse("[download] 100% of 56.43MiB in 00:42"),
+            (
+                Some(100.0),
+                [
+                    "56.43MiB".into(),
+                    String::new(),
+                    String::new(),
+                    String::new()
+                ]
+            )
+        );
+        assert_eq!(
+            parse("[download]   5.0% of ~123.45MiB at  1.20MiB/s ETA 01:23 (frag 5/100)"),
+            (
+                Some(5.0),
+                [
+                    "~123.45MiB".into(),
+                    "1.20MiB/s".into(),
+                    "01:23".into(),
+                    "5 / 100".into()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn parses_fragment_progress_of_unknown_size() {
+        let line = "[download]  123.45MiB at    2.00MiB/s (00:01:02) (frag 17/340)";
+        let progress = ProgressDetail::parse(line, None);
+
+        assert!(progress.is_streaming());
+        assert_eq!(progress.fragment_percent(), Some(5.0));
+        assert_eq!(progress.fragments(), Some((17, 340)));
+        assert_eq!(
+            parse(line),
+            (
+                None,
+                [
+                    String::new(),
+                    "2.00MiB/s".into(),
+                    String::new(),
+                    "17 / 340".into()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn derives_percent_from_fragments_for_estimated_size() {
+        let estimated = ProgressDetail::parse(
+            "[download]  12.3% of ~  1.23GiB at    2.34MiB/s ETA 05:01 (frag 17/340)",
+            None,
+        );
+        assert!(!estimated.is_streaming());
+        assert_eq!(estimated.fragment_percent(), Some(5.0));
+
+        let exact = ProgressDetail::parse(
+            "[download]  12.3% of 1.23GiB at    2.34MiB/s ETA 05:01 (frag 17/340)",
+            None,
+        );
+        assert_eq!(exact.fragment_percent(), None);
+        assert_eq!(exact.fragments(), Some((17, 340)));
+    }
+
+    #[test]
+    fn keeps_other_lines_raw() {
+        assert!(matches!(
+            ProgressDetail::parse("[youtube] abc123: Downloading webpage", Some(12.3)),
+            ProgressDetail::Raw("[youtube] abc123: Downloading webpage")
+        ));
+    }
+
+    #[test]
+    fn retains_size_of_completion_lines_only() {
+        assert_eq!(
+            ProgressDetail::parse(
+                "[download] 100% of   12.34MiB in 00:00:05 at 2.31MiB/s",
+                None
+            )
+            .completed_size(),
+            Some("12.34MiB")
+        );
+        assert_eq!(
+            ProgressDetail::parse("[download]  99.9% of 12.34MiB at 2.31MiB/s ETA 00:01", None)
+                .completed_size(),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_speed_in_bytes_per_second() {
+        let speed = |line| ProgressDetail::parse(line, None).parsed_speed_bytes_per_sec();
+
+        assert_eq!(
+            speed("[download]   0.4% of 56.43MiB at 94.93KiB/s ETA 10:09"),
+            Some(94.93 * 1024.0)
+        );
+        assert_eq!(
+            speed("[download]  12.3% of ~  1.23GiB at ~  2.00MiB/s ETA 05:01 (frag 5/100)"),
+            Some(2.0 * 1024.0 * 1024.0)
+        );
+        assert_eq!(
+            speed("[download]  10.0% of 12.34MiB at 512.00B/s ETA 00:20"),
+            Some(512.0)
+        );
+        assert_eq!(
+            speed("[download]  10.0% of 12.34MiB at Unknown speed ETA Unknown ETA"),
+            None
+        );
+    }
+}
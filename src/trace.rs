@@ -1,19 +1,53 @@
 use clap_verbosity_flag::Verbosity;
 use color_eyre::eyre::{eyre, Result};
-use opentelemetry::KeyValue;
-use opentelemetry_sdk::Resource;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::{metrics::SdkMeterProvider, Resource};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{prelude::*, EnvFilter};
 
-use crate::args::Args;
+use crate::args::GlobalArgs;
 
-pub(crate) fn init(args: &Args) -> Result<WorkerGuard> {
-    // Log file
+// Flushes logs, traces and metrics on drop.
+pub(crate) struct TelemetryGuard {
+    _appender_guard: Option<WorkerGuard>,
+    meter_provider: Option<SdkMeterProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(ref meter_provider) = self.meter_provider {
+            // Export pending traces and metrics. Nothing is left to report failures to.
+            global::shutdown_tracer_provider();
+            meter_provider.shutdown().ok();
+        }
+    }
+}
+
+pub(crate) fn init(args: &GlobalArgs) -> Result<TelemetryGuard> {
+    // Log file, not even created if disabled.
     // TODO: Log into a buffer and display that in a bottom split pane.
-    let file_appender = tracing_appender::rolling::never(".", "showcase-dl.log");
-    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+    let (maybe_non_blocking, appender_guard) = if args.no_log_file {
+        (None, None)
+    } else {
+        let file_appender = tracing_appender::rolling::never(".", "showcase-dl.log");
+        let (non_blocking, appender_guard) = tracing_appender::non_blocking(file_appender);
+        (Some(non_blocking), Some(appender_guard))
+    };
+
+    // Open telemetry metrics export, installed as global meter provider
+    let meter_provider = if args.otlp_export {
+        Some(
+            opentelemetry_otlp::new_pipeline()
+                .metrics(opentelemetry_sdk::runtime::Tokio)
+                .with_exporter(opentelemetry_otlp::new_exporter().http())
+                .with_resource(resource())
+                .build()?,
+        )
+    } else {
+        None
+    };
 
     tracing_subscriber::registry()
         .with(if args.otlp_export {
@@ -22,36 +56,42 @@ pub(crate) fn init(args: &Args) -> Result<WorkerGuard> {
                 let tracer = opentelemetry_otlp::new_pipeline()
                     .tracing()
                     .with_exporter(opentelemetry_otlp::new_exporter().http())
-                    .with_trace_config(opentelemetry_sdk::trace::config().with_resource(
-                        Resource::new(vec![KeyValue::new("service.name", "showcase-dl")]),
-                    ))
+                    .with_trace_config(opentelemetry_sdk::trace::config().with_resource(resource()))
                     // .install_simple()?;
                     .install_batch(opentelemetry_sdk::runtime::Tokio)?;
 
                 // Create a tracing layer with the configured tracer
                 let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
 
-                telemetry.with_filter(env_filter(&args.verbosity))
+                telemetry.with_filter(env_filter(args.verbosity))
             })
         } else {
             None
         })
-        .with(
+        // Each layer has its own filter, so that disabling the log file leaves OTLP export unaffected.
+        .with(maybe_non_blocking.map(|non_blocking| {
             tracing_subscriber::fmt::layer()
                 .pretty()
                 .with_thread_names(true)
                 .with_line_number(true)
                 .with_writer(non_blocking)
-                .with_filter(env_filter(&args.verbosity)),
-        )
+                .with_filter(env_filter(args.verbosity))
+        }))
         .with(ErrorLayer::default())
         .try_init()
         .map_err(|_| eyre!("Tracing initialization failed"))?;
 
-    Ok(guard)
+    Ok(TelemetryGuard {
+        _appender_guard: appender_guard,
+        meter_provider,
+    })
+}
+
+fn resource() -> Resource {
+    Resource::new(vec![KeyValue::new("service.name", "showcase-dl")])
 }
 
-fn env_filter(verbosity: &Verbosity) -> EnvFilter {
+fn env_filter(verbosity: Verbosity) -> EnvFilter {
     // Use `-v` (warn) to `-vvvv` (trace) for simple verbosity,
     // or use `RUST_LOG=target[span{field=value}]=level` for fine-grained verbosity control.
     // See https://docs.rs/tracing-subscriber/latest/tracing_subscriber/filter/struct.EnvFilter.html
@@ -1,7 +1,16 @@
-use std::{borrow::Cow, io, rc::Rc, sync::Arc};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    io,
+    ops::Range,
+    rc::Rc,
+    sync::Arc,
+};
 
 use color_eyre::eyre::{bail, Report, Result};
 use crossterm::{
+    cursor::Show,
     event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -13,28 +22,189 @@ use futures::{
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::Alignment,
+    layout::{Alignment, Constraint, Layout},
     prelude::Rect,
     text::Span,
-    widgets::{Block, BorderType, Borders, Gauge, Row, Table},
+    widgets::{Block, Borders, Clear, Gauge, LineGauge, Paragraph, Row, Sparkline, Table, Wrap},
     Frame, Terminal,
 };
-use tokio::{sync::oneshot, time::MissedTickBehavior};
-use tracing::{error, instrument, Instrument};
+use tokio::{
+    sync::{
+        broadcast::{self, error::TryRecvError},
+        oneshot,
+    },
+    time::{Duration, Instant, Interval, MissedTickBehavior},
+};
+use tracing::{error, instrument, warn, Instrument};
 
-use crate::state::{
-    video::{progress::ProgressDetail, Stage as VideoStage, Video, VideoRead},
-    Stage, State,
+use crate::{
+    session::Event as ProgressEvent,
+    state::{
+        video::{
+            progress::{self, ProgressDetail},
+            Stage as VideoStage, Video, VideoRead,
+        },
+        Stage, State,
+    },
 };
 
 mod layout;
+mod preferences;
 mod style;
+mod text;
+pub(crate) mod theme;
+
+// Slow down rendering after this long without downloader output.
+const IDLE_AFTER: Duration = Duration::from_secs(2);
+
+// Render interval while idle, unless `--tick` is even slower.
+const IDLE_TICK: Duration = Duration::from_millis(250);
+
+// Running downloads are considered stalled after this long without their percentage advancing.
+const STALLED_AFTER: Duration = Duration::from_secs(30);
+
+// Aggregate throughput is sampled about this often, on render.
+const THROUGHPUT_SAMPLE_PERIOD: Duration = Duration::from_secs(1);
+
+// Number of throughput samples shown in the sparkline.
+const THROUGHPUT_SAMPLES: usize = 60;
+
+// Redraw at least this often, even if no state has changed.
+const FORCED_REDRAW: Duration = Duration::from_secs(1);
+
+pub(crate) struct Ui {
+    // Whether finished videos are collapsed into a single summary row.
+    collapse_finished: Cell<bool>,
+
+    // Index of the selected video, if any. Indices are stable, while display positions change with sorting.
+    selected: Cell<Option<usize>>,
+
+    // Whether the info overlay of the selected video is shown.
+    show_info: Cell<bool>,
+
+    // Indices of the videos in order of their display positions, as of the latest render.
+    display_order: RefCell<Vec<usize>>,
+
+    // Maximum number of videos rendered at once, or as many as fit if `None`.
+    page_size: Cell<Option<usize>>,
+
+    // Display position of the first rendered video, following the selection.
+    scroll_offset: Cell<usize>,
+
+    // Number of videos which fit into the terminal, as of the latest render.
+    fitting_count: Cell<usize>,
+
+    // Whether the aggregate throughput sparkline is shown.
+    show_throughput: Cell<bool>,
 
-pub(crate) struct Ui;
+    // Aggregate download speed samples in bytes per second, oldest first.
+    // Sampled while hidden as well, so that the sparkline shows history once toggled on.
+    throughput: RefCell<VecDeque<u64>>,
+
+    // Point in time of the latest throughput sample.
+    throughput_sampled_at: Cell<Instant>,
+}
+
+// Bytes downloaded this session, summed over all videos, including collapsed ones.
+#[derive(Clone, Copy, Default)]
+struct DownloadedBytes {
+    // Total size of finished videos.
+    finished: f64,
+    // Downloaded share of the total size of videos in progress.
+    in_progress: f64,
+}
+
+// Reaction to a terminal input event.
+enum UserAction {
+    Redraw,
+    // Re-layout at the new terminal size right away.
+    Resize,
+    TogglePause,
+    ToggleCollapseFinished,
+    ToggleThroughput,
+    GrowPage,
+    ShrinkPage,
+    SelectPrevious,
+    SelectNext,
+    ToggleInfo,
+    CancelSelected,
+    // Close the info overlay if shown, else quit.
+    Dismiss,
+    Quit,
+}
 
 impl Ui {
-    pub(crate) fn new() -> Self {
-        Ui
+    pub(crate) fn new(theme: theme::Theme, ascii: bool, page_size: Option<usize>) -> Self {
+        theme::init(theme);
+        style::init_ascii(ascii);
+
+        // Restore the view preferences of the previous run.
+        let preferences = preferences::Preferences::load();
+
+        Ui {
+            collapse_finished: Cell::new(preferences.collapse_finished),
+            selected: Cell::new(None),
+            show_info: Cell::new(false),
+            display_order: RefCell::new(vec![]),
+            page_size: Cell::new(page_size),
+            scroll_offset: Cell::new(0),
+            fitting_count: Cell::new(0),
+            show_throughput: Cell::new(preferences.show_throughput),
+            throughput: RefCell::new(VecDeque::with_capacity(THROUGHPUT_SAMPLES)),
+            throughput_sampled_at: Cell::new(Instant::now()),
+        }
+    }
+
+    // Move the selection to the previous or next displayed video, selecting the first if none is selected.
+    fn move_selection(&self, forward: bool) {
+        let display_order = self.display_order.borrow();
+        let Some(last_position) = display_order.len().checked_sub(1) else {
+            return;
+        };
+
+        let position = match self
+            .selected
+            .get()
+            .and_then(|selected| display_order.iter().position(|&index| index == selected))
+        {
+            None => 0,
+            Some(position) if forward => (position + 1).min(last_position),
+            Some(position) => position.saturating_sub(1),
+        };
+
+        self.selected.set(Some(display_order[position]));
+    }
+
+    // Render one more video at once, up to as many as fit.
+    fn grow_page(&self) {
+        self.page_size.set(
+            self.page_size
+                .get()
+                .map(|page_size| page_size + 1)
+                .filter(|&page_size| page_size < self.fitting_count.get()),
+        );
+    }
+
+    // Render one video less at once, but at least one.
+    fn shrink_page(&self) {
+        let page_size = self
+            .page_size
+            .get()
+            .unwrap_or(usize::MAX)
+            .min(self.fitting_count.get());
+        self.page_size.set(Some(page_size.saturating_sub(1).max(1)));
+    }
+
+    // Consume all pending events, returning whether anything has been published since the last call.
+    fn drain_events(events: &mut broadcast::Receiver<ProgressEvent>) -> bool {
+        let mut updated = false;
+        loop {
+            match events.try_recv() {
+                // Missed events are irrelevant, as the next redraw reads the full state.
+                Ok(_) | Err(TryRecvError::Lagged(_)) => updated = true,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return updated,
+            }
+        }
     }
 
     #[instrument(skip(self, state, do_work))]
@@ -42,6 +212,7 @@ impl Ui {
         &self,
         state: Arc<State>,
         tick: u64,
+        exit_on_done: bool,
         do_work: impl Future<Output = Result<()>>,
     ) -> Result<()> {
         let mut terminal = Self::take_terminal()?;
@@ -55,11 +226,23 @@ impl Ui {
             // Stream input events (Keyboard, Mouse, Resize)
             let mut event_stream = EventStream::new();
 
-            // Prepare render tick interval
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(tick));
-            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // Prepare render tick interval, which slows down while idle.
+            let active_period = Duration::from_millis(tick);
+            let idle_period = active_period.max(IDLE_TICK);
+            let mut period = active_period;
+            let mut interval = Self::render_interval(period);
 
-            self.render(&state, &mut terminal).await?;
+            // Count render ticks to drive animations, such as the spinner.
+            let mut render_tick: usize = 0;
+
+            // Video updates arrive as events, while each redraw reads a full snapshot of the state.
+            let mut events = state.subscribe();
+
+            Self::recover_from_draw_error(
+                &state,
+                self.render(&state, &mut terminal, render_tick).await,
+            )?;
+            let mut last_redraw = Instant::now();
 
             let (abort_handle, abort_registration) = AbortHandle::new_pair();
             let do_work_abortable = Abortable::new(
@@ -68,14 +251,20 @@ impl Ui {
                 abort_registration,
             );
 
+            // Set once the application process futures have completed,
+            // so that `exit_on_done` does not cut short any work following `Stage::Done`.
+            let work_done = Cell::new(false);
+
             tokio::try_join!(
                 async {
                     // Drive application process futures, aborting in reaction to user request.
-                    match do_work_abortable.await {
+                    let result = match do_work_abortable.await {
                         Ok(result) => result,
                         // Swallow futures::future::Aborted error.
                         Err(Aborted) => Ok(()),
-                    }
+                    };
+                    work_done.set(true);
+                    result
                 },
                 async {
                     let (tx_shutdown_complete, mut rx_shutdown_complete) = oneshot::channel::<()>();
@@ -91,24 +280,108 @@ impl Ui {
                             // Handle streamed input events as they occur
                             maybe_event = event_stream.next() => match maybe_event {
 
-                                // Shutdown on request by breaking out of the event loop
-                                Some(Ok(ref event)) => if ! Self::handle_event(event) {
+                                Some(Ok(ref event)) => match Self::handle_event(event) {
+                                    UserAction::Redraw => {
+                                        // Render user interaction, such as mouse events, promptly.
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
+
+                                    // Waiting for the next render tick would leave a stale, garbled layout on screen,
+                                    // for up to a full idle tick.
+                                    UserAction::Resize => {
+                                        state.record_activity();
+                                        Self::recover_from_draw_error(
+                                            &state,
+                                            self.render(&state, &mut terminal, render_tick).await,
+                                        )?;
+                                        last_redraw = Instant::now();
+                                    }
+
+                                    // Pause or resume all downloads, unless shutting down.
+                                    UserAction::TogglePause => if shutdown_signal.is_some() {
+                                        let state = state.clone();
+                                        tokio::spawn(
+                                            async move { state.toggle_pause().await }.in_current_span()
+                                        );
+                                    },
+
+                                    UserAction::ToggleCollapseFinished => {
+                                        self.collapse_finished.set(!self.collapse_finished.get());
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
+
+                                    UserAction::ToggleThroughput => {
+                                        self.show_throughput.set(!self.show_throughput.get());
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
+
+                                    action @ (UserAction::GrowPage | UserAction::ShrinkPage) => {
+                                        if matches!(action, UserAction::GrowPage) {
+                                            self.grow_page();
+                                        } else {
+                                            self.shrink_page();
+                                        }
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
+
+                                    action @ (UserAction::SelectPrevious | UserAction::SelectNext) => {
+                                        self.move_selection(matches!(action, UserAction::SelectNext));
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
 
-                                    // Intiate shutdown only once, silently ignore user shutdown requests
-                                    // while awaiting child processes muxing livestream data.
-                                    if let Some(tx_shutdown_complete) = shutdown_signal.take() {
+                                    // Show or hide the info overlay, selecting the first video if none is selected.
+                                    UserAction::ToggleInfo => {
+                                        if self.selected.get().is_none() {
+                                            self.move_selection(true);
+                                        }
+                                        self.show_info.set(!self.show_info.get());
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
 
-                                        // Refuse to start new downloads and send SIGINT to existing children.
-                                        // Initiate shutdown on a new task, then keep looping & rendering.
+                                    // Cancel the selected video if queued, or interrupt it if running.
+                                    UserAction::CancelSelected => if let Some(index) = self.selected.get() {
                                         let state = state.clone();
                                         tokio::spawn(
                                             async move {
-                                                match state.initiate_shutdown(tx_shutdown_complete).await {
-                                                    Ok(()) => {},
-                                                    Err(e) => error!("{e}"),
+                                                if let Some(video) = state.video(index).await {
+                                                    if let Err(e) = video.cancel(state.shutdown_signal).await {
+                                                        error!("{e}");
+                                                    }
                                                 }
-                                             }.in_current_span()
+                                            }.in_current_span()
                                         );
+                                    },
+
+                                    UserAction::Dismiss if self.show_info.get() => {
+                                        self.show_info.set(false);
+                                        state.record_activity();
+                                        state.mark_changed();
+                                    }
+
+                                    // Shutdown on request by breaking out of the event loop
+                                    UserAction::Dismiss | UserAction::Quit => {
+                                        // Intiate shutdown only once, silently ignore user shutdown requests
+                                        // while awaiting child processes muxing livestream data.
+                                        if let Some(tx_shutdown_complete) = shutdown_signal.take() {
+
+                                            // Refuse to start new downloads and send SIGINT to existing children.
+                                            // Initiate shutdown on a new task, then keep looping & rendering.
+                                            let state = state.clone();
+                                            tokio::spawn(
+                                                async move {
+                                                    match state.initiate_shutdown(tx_shutdown_complete).await {
+                                                        Ok(()) => {},
+                                                        Err(e) => error!("{e}"),
+                                                    }
+                                                 }.in_current_span()
+                                            );
+                                        }
                                     }
                                 },
                                 // Event reader poll error, e.g. initialization failure, or interrupt
@@ -117,14 +390,39 @@ impl Ui {
                                 None => break,
                             },
 
-                            // Note: We *might* also want to break out of the event loop
-                            //       as soon as `state.stage()` switches to `Stage::Done`.
-                            //       ...
-                            // TODO: Implement that? Or prefer keeping the app open
-                            //        until explicitly closed by the user? (Esc, Q or Ctrl+C)
-
                             // Render every N milliseconds
-                            _ = interval.tick() => self.render(&state, &mut terminal).await?
+                            _ = interval.tick() => {
+                                // Skip drawing while nothing has changed, but redraw periodically regardless.
+                                if Self::drain_events(&mut events)
+                                    || state.take_changed()
+                                    || state.is_animated().await
+                                    || last_redraw.elapsed() >= FORCED_REDRAW
+                                {
+                                    render_tick = render_tick.wrapping_add(1);
+                                    Self::recover_from_draw_error(
+                                        &state,
+                                        self.render(&state, &mut terminal, render_tick).await,
+                                    )?;
+                                    last_redraw = Instant::now();
+                                }
+
+                                // Adapt the render interval to download activity, saving CPU while idle.
+                                let next_period = if state.idle_duration() >= IDLE_AFTER {
+                                    idle_period
+                                } else {
+                                    active_period
+                                };
+                                if next_period != period {
+                                    period = next_period;
+                                    interval = Self::render_interval(period);
+                                }
+
+                                // By default, keep the app open until explicitly closed by the user (Esc, Q or Ctrl+C).
+                                // If requested, exit as soon as all work is done and no child process is left running.
+                                if exit_on_done && work_done.get() && state.is_done().await {
+                                    break;
+                                }
+                            }
                         }
                     }
 
@@ -143,13 +441,51 @@ impl Ui {
         // First release the terminal, then propagate a possible `Err(Report)` from the `do_work` future.
         Self::release_terminal(terminal)?;
 
+        preferences::Preferences {
+            collapse_finished: self.collapse_finished.get(),
+            show_throughput: self.show_throughput.get(),
+        }
+        .save();
+
         // Print a clean backtrace on failure.
         result_while_captured_terminal?;
 
         Ok(())
     }
 
-    pub(crate) fn make_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    // Terminal hiccups, such as a resize during draw, must not tear down long running downloads.
+    // Transient draw errors are logged and the frame is redrawn on the next render tick.
+    // Only the loss of the terminal itself is fatal.
+    fn recover_from_draw_error(state: &State, result: io::Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) if Self::is_terminal_lost(&e) => bail!("lost the terminal: {e}"),
+            Err(e) => {
+                warn!("Failed to draw the terminal user interface, retrying on the next render tick: {e}");
+                state.mark_changed();
+                Ok(())
+            }
+        }
+    }
+
+    fn is_terminal_lost(error: &io::Error) -> bool {
+        matches!(
+            error.kind(),
+            io::ErrorKind::BrokenPipe
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::WriteZero
+        )
+    }
+
+    fn render_interval(period: Duration) -> Interval {
+        let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    }
+
+    fn make_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
         let backend = CrosstermBackend::new(io::stdout());
         Ok(Terminal::new(backend)?)
     }
@@ -168,12 +504,24 @@ impl Ui {
         disable_raw_mode()
     }
 
-    fn handle_event(event: &Event) -> bool {
+    // Restore the shell without constructing a terminal, which may itself fail, e.g. in the panic hook.
+    // Each step is attempted regardless of earlier failures, as nothing is left to report them to.
+    pub(crate) fn restore_terminal() {
+        execute!(io::stdout(), Show, LeaveAlternateScreen).ok();
+        disable_raw_mode().ok();
+    }
+
+    fn handle_event(event: &Event) -> UserAction {
         match event {
-            // Handle keyboard event: Exit on Esc, Q or Ctrl+C
+            // Close the info overlay on Esc, or exit if none is shown
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc, ..
+            }) => UserAction::Dismiss,
+
+            // Handle keyboard event: Exit on Q or Ctrl+C
             Event::Key(
                 KeyEvent {
-                    code: KeyCode::Esc | KeyCode::Char('q'),
+                    code: KeyCode::Char('q'),
                     modifiers: _,
                     ..
                 }
@@ -182,34 +530,94 @@ impl Ui {
                     modifiers: KeyModifiers::CONTROL,
                     ..
                 },
-            ) => false,
+            ) => UserAction::Quit,
+
+            // Pause or resume all downloads on P
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('p'),
+                ..
+            }) => UserAction::TogglePause,
+
+            // Collapse or expand finished videos on F
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('f'),
+                ..
+            }) => UserAction::ToggleCollapseFinished,
+
+            // Show or hide the aggregate throughput sparkline on S
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('s'),
+                ..
+            }) => UserAction::ToggleThroughput,
+
+            // Render more or fewer videos at once on + and -
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('+'),
+                ..
+            }) => UserAction::GrowPage,
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('-'),
+                ..
+            }) => UserAction::ShrinkPage,
+
+            // Select videos with the arrow keys, or with K and J
+            Event::Key(KeyEvent {
+                code: KeyCode::Up | KeyCode::Char('k'),
+                ..
+            }) => UserAction::SelectPrevious,
+            Event::Key(KeyEvent {
+                code: KeyCode::Down | KeyCode::Char('j'),
+                ..
+            }) => UserAction::SelectNext,
+
+            // Show or hide info on the selected video on I
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('i'),
+                ..
+            }) => UserAction::ToggleInfo,
+
+            // Cancel or interrupt the selected video on D
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('d'),
+                ..
+            }) => UserAction::CancelSelected,
 
-            // Handle other keyboard events later, e.g. to
-            // select list items or scroll in long tables
+            Event::Resize(..) => UserAction::Resize,
+
+            // Handle other keyboard events later, e.g. to scroll in long tables
             // Event::Key(_) => true,
 
-            // Mouse & Resize events
-            _ => true,
+            // Mouse events
+            _ => UserAction::Redraw,
         }
     }
 
-    async fn render<'a>(
+    async fn render(
         &self,
         state: &State,
-        terminal: &'a mut Terminal<CrosstermBackend<io::Stdout>>,
-    ) -> Result<()> {
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        render_tick: usize,
+    ) -> io::Result<()> {
         // The terminal's `draw()` method runs a sync closure, so we need to acquire all
         // read guards before we can start rendering.
         // First, the videos vec is locked to prevent new videos from being added.
         // Then, each video is asked to acquire read on its
 
+        let run_duration = text::format_duration(state.run_duration());
         let app_title = match *state.stage().await {
-            Stage::Initializing => Cow::Borrowed(" INITIALIZING ... "),
-            Stage::FetchingSource(ref url) => {
-                Cow::Owned(format!(" FETCHING SOURCE PAGE '{url}' ... "))
+            Stage::Initializing => Cow::Owned(format!(
+                " {} INITIALIZING ... ",
+                style::spinner_frame(render_tick)
+            )),
+            Stage::FetchingSource(ref url) => Cow::Owned(format!(
+                " {} FETCHING SOURCE PAGE '{url}' ... ",
+                style::spinner_frame(render_tick)
+            )),
+            Stage::Processing if state.is_paused() => {
+                Cow::Owned(format!(" PAUSED - PRESS 'P' TO RESUME · {run_duration} "))
             }
-            Stage::Processing => Cow::Borrowed(" VIMEO SHOWCASE DOWNLOAD "),
-            Stage::Done => Cow::Borrowed(" FINISHED! "),
+            Stage::Processing => Cow::Owned(format!(" VIMEO SHOWCASE DOWNLOAD · {run_duration} ")),
+            Stage::Done => Cow::Owned(format!(" FINISHED! · {run_duration} ")),
             Stage::ShuttingDown => Cow::Borrowed(" SHUTTING DOWN - PLEASE WAIT ... "),
         };
 
@@ -217,26 +625,80 @@ impl Ui {
         let all_videos = state.videos().await;
 
         // Acquire read on collected video read guards to render all in a sync(!) closure.
-        let all_videos_read = Self::acquire_all_videos_sorted(all_videos.iter()).await;
+        // All videos are read, not only those of the page, as sorting and the status line counts need each video.
+        let (all_videos_read, collapsed_count, downloaded_bytes) =
+            Self::acquire_all_videos_sorted(all_videos.iter(), self.collapse_finished.get()).await;
+        let sections = layout::Sections {
+            has_throughput: self.show_throughput.get(),
+            has_summary: collapsed_count > 0,
+        };
+
+        self.sample_throughput(&all_videos_read);
+
+        let notices = state.notices();
+
+        let status_line = Self::status_line(&all_videos_read, collapsed_count, downloaded_bytes);
 
         terminal.draw(|frame| {
             let area = frame.area();
 
-            let chunks = layout::layout_chunks(area, &all_videos_read);
+            // Rather than rendering overlapping, clipped widgets, ask for a larger terminal.
+            if area.height < layout::MIN_HEIGHT {
+                Self::render_terminal_too_small(frame, area);
+                return;
+            }
+
+            let page = self.paginate(area, &all_videos_read, sections);
+            let visible_videos_read = &all_videos_read[page.clone()];
+
+            let status_line = Self::page_status_line(&status_line, &page, all_videos_read.len());
+
+            let chunks = layout::layout_chunks(area, visible_videos_read, sections);
 
             Self::render_app_frame(frame, &chunks, app_title);
 
-            for (i, video) in all_videos_read.iter().enumerate() {
+            if sections.has_throughput {
+                self.render_throughput(frame, &chunks);
+            }
+
+            if sections.has_summary {
+                Self::render_collapsed_summary(frame, &chunks, sections, collapsed_count);
+            }
+
+            Self::render_status_line(frame, &chunks, &status_line);
+
+            // Render notices below the videos, in the remaining space.
+            if !notices.is_empty() {
+                Self::render_notices(frame, &chunks, &notices);
+            }
+
+            for (i, video) in visible_videos_read.iter().enumerate() {
                 // TODO: Create a video widget?
                 // TODO: Make video widget selectable, expose pause, continue, stop (SIGINT), retry
                 // TODO: Create a scrollable(!) "list of videos" widget
 
-                let chunk_start = 1 + i * layout::CHUNKS_PER_VIDEO;
+                let chunk_start =
+                    layout::first_video_chunk(sections) + i * layout::CHUNKS_PER_VIDEO;
 
-                Self::render_video_title(frame, &chunks, chunk_start, video);
+                Self::render_video_title(
+                    frame,
+                    &chunks,
+                    chunk_start,
+                    video,
+                    state.download_attempts,
+                    video.index().is_some() && video.index() == self.selected.get(),
+                );
 
+                // Fragment-based downloads of unknown size, such as livestreams, report no byte percentage.
+                // Their progress is represented by the share of downloaded fragments instead.
                 let display_percent = video
                     .percent_done()
+                    .or_else(|| {
+                        video
+                            .progress_detail()
+                            .as_ref()
+                            .and_then(ProgressDetail::fragment_percent)
+                    })
                     .unwrap_or_else(|| Self::video_percent_done_default(video.stage()));
 
                 // Video raw progress text or parsed progress
@@ -246,6 +708,7 @@ impl Ui {
                     chunk_start,
                     video,
                     display_percent,
+                    render_tick,
                 );
 
                 // Video progress bar
@@ -260,16 +723,62 @@ impl Ui {
                 // Video bottom margin
                 // (not rendered)
             }
+
+            // Render the info overlay on top of everything else.
+            if self.show_info.get() {
+                if let Some(video) = all_videos_read
+                    .iter()
+                    .find(|video| video.index().is_some() && video.index() == self.selected.get())
+                {
+                    Self::render_video_info(frame, area, video);
+                }
+            }
         })?;
 
         Ok(())
     }
 
+    // Display positions of the videos to render: those which fit into the terminal as a whole,
+    // at most a page of them, scrolled to the selected video.
+    fn paginate(
+        &self,
+        area: Rect,
+        videos: &[VideoRead<'_>],
+        sections: layout::Sections,
+    ) -> Range<usize> {
+        let fitting_count = layout::visible_video_count(area, videos.len(), sections);
+        self.fitting_count.set(fitting_count);
+        let page_len = self
+            .page_size
+            .get()
+            .map_or(fitting_count, |page_size| page_size.min(fitting_count));
+
+        let maybe_selected_position = videos
+            .iter()
+            .position(|video| video.index().is_some() && video.index() == self.selected.get());
+        let scroll_offset = layout::scroll_offset(
+            self.scroll_offset.get(),
+            page_len,
+            videos.len(),
+            maybe_selected_position,
+        );
+        self.scroll_offset.set(scroll_offset);
+
+        // Remember the display order, to move the selection along, beyond the rendered videos.
+        self.display_order
+            .replace(videos.iter().filter_map(VideoRead::index).collect());
+
+        scroll_offset..scroll_offset + page_len
+    }
+
     /// Acquire read on collected video read guards to render all in a sync(!) closure.
     /// The collection is returned sorted by title - where available - else URL.
+    // Returns the sorted videos to render, along with the number of collapsed finished videos,
+    // and the bytes downloaded by all videos, including collapsed ones.
     async fn acquire_all_videos_sorted(
         videos: core::slice::Iter<'_, Arc<Video>>,
-    ) -> Vec<VideoRead> {
+        collapse_finished: bool,
+    ) -> (Vec<VideoRead<'_>>, usize, DownloadedBytes) {
         // Acquire read guards for all videos, to render full state.
         let mut all_videos_read: Vec<VideoRead> = stream::iter(videos)
             .map(|video| async { video.read().await })
@@ -277,16 +786,248 @@ impl Ui {
             .collect()
             .await;
 
+        let downloaded_bytes = Self::downloaded_bytes(&all_videos_read);
+
+        // Failed and running videos always stay expanded, to keep problems visible.
+        let mut collapsed_count = 0;
+        if collapse_finished {
+            all_videos_read.retain(|video_read| {
+                let is_finished = matches!(video_read.stage(), VideoStage::Finished);
+                collapsed_count += usize::from(is_finished);
+                !is_finished
+            });
+        }
+
         // Sort the list of videos by their titles (where available, falling back to URLs).
         (*all_videos_read).sort_by_cached_key(|video_read| {
             if let Some(title) = video_read.title() {
-                title.to_string()
+                title.clone()
             } else {
                 video_read.url().to_string()
             }
         });
 
-        all_videos_read
+        (all_videos_read, collapsed_count, downloaded_bytes)
+    }
+
+    // Sum the sizes of finished videos, and the downloaded shares of videos in progress.
+    // Videos of unknown size, such as livestreams, are left out.
+    // Bytes downloaded before resuming a partial download, e.g. by a previous run, are left out, too.
+    fn downloaded_bytes(videos: &[VideoRead<'_>]) -> DownloadedBytes {
+        let mut downloaded_bytes = DownloadedBytes::default();
+
+        for video in videos {
+            #[allow(clippy::cast_precision_loss)] // Sizes are shown rounded anyway.
+            let resumed_at_byte = video.resumed_at_byte().unwrap_or_default() as f64;
+            match video.stage() {
+                VideoStage::Finished => {
+                    if let Some(final_size) = video
+                        .final_size()
+                        .and_then(|final_size| progress::parse_bytes(final_size))
+                    {
+                        downloaded_bytes.finished += (final_size - resumed_at_byte).max(0.0);
+                    }
+                }
+                VideoStage::Running { .. }
+                | VideoStage::Paused { .. }
+                | VideoStage::ShuttingDown => {
+                    let maybe_size = video
+                        .progress_detail()
+                        .as_ref()
+                        .and_then(ProgressDetail::parsed_size_bytes);
+                    if let (Some(size), Some(percent_done)) = (maybe_size, video.percent_done()) {
+                        downloaded_bytes.in_progress +=
+                            (size * percent_done / 100.0 - resumed_at_byte).max(0.0);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        downloaded_bytes
+    }
+
+    fn render_terminal_too_small(frame: &mut Frame<'_>, area: Rect) {
+        frame.render_widget(
+            Paragraph::new(format!(
+                "Terminal too small (need at least {} rows)",
+                layout::MIN_HEIGHT
+            ))
+            .style(style::application_title_style())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+
+    fn render_collapsed_summary(
+        frame: &mut Frame<'_>,
+        chunks: &Rc<[Rect]>,
+        sections: layout::Sections,
+        collapsed_count: usize,
+    ) {
+        frame.render_widget(
+            Paragraph::new(format!("{collapsed_count} finished (press 'F' to expand)"))
+                .style(style::video_stage_style(&VideoStage::Finished)),
+            chunks[layout::summary_chunk(sections)],
+        );
+    }
+
+    // Record the summed download speed of all running videos, at most once per sample period.
+    fn sample_throughput(&self, videos: &[VideoRead<'_>]) {
+        if self.throughput_sampled_at.get().elapsed() < THROUGHPUT_SAMPLE_PERIOD {
+            return;
+        }
+        self.throughput_sampled_at.set(Instant::now());
+
+        let bytes_per_sec: f64 = videos
+            .iter()
+            .filter(|video| matches!(video.stage(), VideoStage::Running { .. }))
+            .filter_map(|video| {
+                video
+                    .progress_detail()
+                    .as_ref()
+                    .and_then(ProgressDetail::parsed_speed_bytes_per_sec)
+            })
+            .sum();
+
+        let mut throughput = self.throughput.borrow_mut();
+        if throughput.len() == THROUGHPUT_SAMPLES {
+            throughput.pop_front();
+        }
+        // Speeds are never negative, and far below `u64::MAX`.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        throughput.push_back(bytes_per_sec as u64);
+    }
+
+    // Sparkline of the aggregate download speed, labelled with the latest sample.
+    fn render_throughput(&self, frame: &mut Frame<'_>, chunks: &Rc<[Rect]>) {
+        let throughput = self.throughput.borrow();
+
+        // Samples are whole bytes per second, well within the precise range of `f64`.
+        #[allow(clippy::cast_precision_loss)]
+        let latest = throughput.back().copied().unwrap_or_default() as f64;
+        let label = format!("Throughput {} ", text::format_speed(latest));
+
+        let [label_area, sparkline_area] = Layout::horizontal([
+            Constraint::Length(u16::try_from(label.len()).unwrap_or(u16::MAX)),
+            Constraint::Min(0),
+        ])
+        .areas(chunks[layout::throughput_chunk()]);
+
+        frame.render_widget(
+            Paragraph::new(label).style(style::table_header_style()),
+            label_area,
+        );
+
+        // Show the latest samples which fit, the newest at the right edge.
+        let (older, newer) = throughput.as_slices();
+        let samples: Vec<u64> = older.iter().chain(newer).copied().collect();
+        let visible = &samples[samples
+            .len()
+            .saturating_sub(usize::from(sparkline_area.width))..];
+
+        frame.render_widget(
+            Sparkline::default()
+                .bar_set(style::sparkline_bar_set())
+                .data(visible)
+                .style(style::throughput_style()),
+            sparkline_area,
+        );
+    }
+
+    // Summarize the number of videos by stage, e.g. "Init 2 · Queued 5 · Running 4 · Finished 18 · Failed 1".
+    // Collapsed videos are finished, and counted as such.
+    fn status_line(
+        videos: &[VideoRead<'_>],
+        collapsed_count: usize,
+        downloaded_bytes: DownloadedBytes,
+    ) -> String {
+        let (mut initializing, mut queued, mut running, mut paused, mut shutting_down) =
+            (0, 0, 0, 0, 0);
+        let (mut finished, mut failed, mut cancelled, mut skipped, mut geo_blocked) =
+            (collapsed_count, 0, 0, 0, 0);
+
+        for video in videos {
+            match video.stage() {
+                VideoStage::Initializing => initializing += 1,
+                VideoStage::Queued => queued += 1,
+                VideoStage::Running { .. } => running += 1,
+                VideoStage::Paused { .. } => paused += 1,
+                VideoStage::ShuttingDown => shutting_down += 1,
+                VideoStage::Finished => finished += 1,
+                VideoStage::Failed => failed += 1,
+                VideoStage::Cancelled => cancelled += 1,
+                VideoStage::Skipped { .. } => skipped += 1,
+                VideoStage::GeoBlocked { .. } => geo_blocked += 1,
+            }
+        }
+
+        let mut counts = vec![
+            format!("Init {initializing}"),
+            format!("Queued {queued}"),
+            format!("Running {running}"),
+        ];
+        // Transient stages are only listed while they occur.
+        if paused > 0 {
+            counts.push(format!("Paused {paused}"));
+        }
+        if shutting_down > 0 {
+            counts.push(format!("Shutting down {shutting_down}"));
+        }
+        counts.push(format!("Finished {finished}"));
+        counts.push(format!("Failed {failed}"));
+        if cancelled > 0 {
+            counts.push(format!("Cancelled {cancelled}"));
+        }
+        if skipped > 0 {
+            counts.push(format!("Skipped {skipped}"));
+        }
+        if geo_blocked > 0 {
+            counts.push(format!("Geo-blocked {geo_blocked}"));
+        }
+        if downloaded_bytes.finished > 0.0 || downloaded_bytes.in_progress > 0.0 {
+            counts.push(format!(
+                "Downloaded {} (+ {} in progress)",
+                text::format_bytes(downloaded_bytes.finished),
+                text::format_bytes(downloaded_bytes.in_progress)
+            ));
+        }
+
+        counts.join(" · ")
+    }
+
+    // Tell which videos are rendered, if not all of them, e.g. "... · showing 1–20 of 134".
+    fn page_status_line<'s>(
+        status_line: &'s str,
+        page: &Range<usize>,
+        video_count: usize,
+    ) -> Cow<'s, str> {
+        if page.len() < video_count {
+            Cow::Owned(format!(
+                "{status_line} · showing {}–{} of {video_count}",
+                page.start + 1,
+                page.end
+            ))
+        } else {
+            Cow::Borrowed(status_line)
+        }
+    }
+
+    fn render_status_line(frame: &mut Frame<'_>, chunks: &Rc<[Rect]>, status_line: &str) {
+        frame.render_widget(
+            Paragraph::new(status_line).style(style::status_line_style()),
+            chunks[layout::status_chunk(chunks)],
+        );
+    }
+
+    fn render_notices(frame: &mut Frame<'_>, chunks: &Rc<[Rect]>, notices: &[String]) {
+        frame.render_widget(
+            Paragraph::new(notices.join("\n"))
+                .style(style::notice_style())
+                .wrap(Wrap { trim: true }),
+            chunks[layout::notices_chunk(chunks)],
+        );
     }
 
     fn render_app_frame(frame: &mut Frame<'_>, chunks: &Rc<[Rect]>, app_title: Cow<'_, str>) {
@@ -295,6 +1036,7 @@ impl Ui {
                 .widths(layout::video_progress_detail_table_layout())
                 .header(
                     Row::new([
+                        "#",
                         "Stage",
                         "Progress",
                         "Destination",
@@ -313,75 +1055,226 @@ impl Ui {
                         .title_alignment(Alignment::Center)
                         .borders(Borders::TOP)
                         .border_style(style::border_style())
-                        .border_type(BorderType::Thick),
+                        .border_set(style::thick_border_set()),
                 ),
             chunks[0],
         );
     }
 
+    fn render_video_info(frame: &mut Frame<'_>, area: Rect, video: &VideoRead<'_>) {
+        let info = format!(
+            "Title: {}\n\nURL: {}\n\nStage: {}{}\n\nProcess ID: {}\n\nThumbnail: {}\n\nOutput file: {}\n\nSubtitle files: {}\n\nCommand: {}",
+            video.title().map_or("-", String::as_str),
+            video.url(),
+            video.stage().name(),
+            // Explain why the video was not downloaded, if known.
+            video
+                .stage()
+                .skip_reason()
+                .or_else(|| video.stage().geo_block_reason())
+                .map(|reason| format!(" ({reason})"))
+                .unwrap_or_default(),
+            // For inspecting the downloader process from another terminal, e.g. with `strace -p`.
+            video
+                .stage()
+                .process_id()
+                .map_or(Cow::Borrowed("-"), |process_id| Cow::Owned(process_id.to_string())),
+            video.thumbnail_url().unwrap_or("n/a"),
+            video.output_file().map_or("-", String::as_str),
+            if video.subtitle_files().is_empty() {
+                Cow::Borrowed("-")
+            } else {
+                Cow::Owned(video.subtitle_files().join(", "))
+            },
+            video.command().map_or("(not yet spawned)", String::as_str),
+        );
+
+        let info_area = layout::overlay_area(area);
+
+        // Clear the videos underneath, rather than blending with them.
+        frame.render_widget(Clear, info_area);
+        frame.render_widget(
+            Paragraph::new(info)
+                .style(style::info_style())
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .title(Span::styled(
+                            " VIDEO INFO - PRESS 'I' OR 'ESC' TO CLOSE ",
+                            style::application_title_style(),
+                        ))
+                        .title_alignment(Alignment::Center)
+                        .borders(Borders::ALL)
+                        .border_style(style::border_style())
+                        .border_set(style::thick_border_set()),
+                ),
+            info_area,
+        );
+    }
+
     fn render_video_title(
         frame: &mut Frame<'_>,
         chunks: &Rc<[Rect]>,
         chunk_start: usize,
         video: &VideoRead<'_>,
+        download_attempts: u8,
+        is_selected: bool,
     ) {
+        let title_chunk = chunks[chunk_start];
+
+        // Make retried downloads visible, keeping first attempts clean.
+        let attempt_suffix = if video.attempt() > 1 {
+            Cow::Owned(format!(
+                " (attempt {}/{download_attempts})",
+                video.attempt()
+            ))
+        } else {
+            Cow::Borrowed("")
+        };
+
+        // Leave room for the trailing space, which separates the title from the border line.
+        let max_width = usize::from(title_chunk.width)
+            .saturating_sub(1)
+            .saturating_sub(attempt_suffix.len());
+
+        // On very narrow terminals, the URL's last path segment is more telling than a truncated title.
+        let title = if max_width < layout::MIN_TITLE_WIDTH {
+            text::last_path_segment(video.url())
+        } else {
+            match video.title() {
+                Some(title) => title.as_str(),
+                None => video.url(),
+            }
+        };
+
         // Video title block
         frame.render_widget(
             Block::default()
                 .title(Span::styled(
                     format!(
-                        "{} ",
-                        match video.title() {
-                            Some(title) => title.as_str(),
-                            None => video.url(),
-                        }
+                        "{}{attempt_suffix} ",
+                        text::truncate_with_ellipsis(title, max_width)
                     ),
-                    style::video_title_style(),
+                    if is_selected {
+                        style::selected_video_title_style()
+                    } else if matches!(video.stage(), VideoStage::Cancelled) {
+                        style::cancelled_video_title_style()
+                    } else {
+                        style::video_title_style()
+                    },
                 ))
                 .borders(Borders::TOP)
                 .border_style(style::border_style())
-                .border_type(BorderType::Plain),
-            chunks[chunk_start],
+                .border_set(style::plain_border_set()),
+            title_chunk,
         );
     }
 
+    // Label of the video's stage, animated while the video is still initializing.
+    fn video_stage_label(
+        video: &VideoRead<'_>,
+        maybe_progress_detail: Option<&ProgressDetail<'_>>,
+        is_stalled: bool,
+        render_tick: usize,
+    ) -> Cow<'static, str> {
+        match video.stage() {
+            VideoStage::Initializing => Cow::Owned(format!(
+                "{} Initializing...",
+                style::spinner_frame(render_tick)
+            )),
+            VideoStage::Queued => Cow::Borrowed("Queued..."),
+            // Downloaded to 100 %, but the output file is not yet complete.
+            VideoStage::Running { .. } if video.is_merging() => Cow::Borrowed("Merging..."),
+            VideoStage::Running { .. }
+                if maybe_progress_detail.is_some_and(ProgressDetail::is_streaming) =>
+            {
+                Cow::Borrowed("Streaming...")
+            }
+            // The downloader's own retries, e.g. of fragments, as opposed to the attempts shown in the title.
+            VideoStage::Running { .. } if video.downloader_retry().is_some() => {
+                Cow::Owned(format!(
+                    "{} Fragment retry {}",
+                    style::retry_symbol(),
+                    video.downloader_retry().unwrap_or_default()
+                ))
+            }
+            VideoStage::Running { .. } if is_stalled => {
+                Cow::Owned(format!("{} Stalled", style::warning_symbol()))
+            }
+            // Partially downloaded files left by a previous run are resumed, rather than restarted.
+            VideoStage::Running { .. } if video.resumed_at_byte().is_some() => {
+                Cow::Borrowed("Resuming...")
+            }
+            VideoStage::Running { .. } => Cow::Borrowed("Running..."),
+            VideoStage::Paused { .. } => Cow::Borrowed("Paused"),
+            VideoStage::ShuttingDown => Cow::Borrowed("Shutting down..."),
+            VideoStage::Finished if video.is_simulated() => Cow::Borrowed("Simulated!"),
+            VideoStage::Finished => Cow::Borrowed("Finished!"),
+            VideoStage::Failed => Cow::Borrowed("Failed!"),
+            VideoStage::Cancelled => Cow::Borrowed("Cancelled"),
+            VideoStage::Skipped { .. } => Cow::Borrowed("Skipped"),
+            VideoStage::GeoBlocked { .. } => Cow::Borrowed("Geo-blocked"),
+        }
+    }
+
     fn render_video_progress_detail(
         frame: &mut Frame<'_>,
         chunks: &Rc<[Rect]>,
         chunk_start: usize,
         video: &VideoRead<'_>,
         display_percent: f64,
+        render_tick: usize,
     ) {
         let progress_detail_chunk = chunks[chunk_start + 1];
         let maybe_progress_detail = video.progress_detail();
+
+        let is_stalled = Self::is_stalled(video);
+
+        // Column "Stage"
+        let stage_label = Self::video_stage_label(
+            video,
+            maybe_progress_detail.as_ref(),
+            is_stalled,
+            render_tick,
+        );
+        let stage_style = if is_stalled {
+            style::stalled_style()
+        } else {
+            style::video_stage_style(video.stage())
+        };
+
+        // Column "#", in order of discovery, which is stable even though videos are sorted by title.
+        let index_cell = Span::raw(
+            video
+                .index()
+                .map(|index| index.to_string())
+                .unwrap_or_default(),
+        );
+
         if let Some(progress) = &maybe_progress_detail {
             // Build two variants of details table, depending on if we have a
             // `ProgressDetail::Raw(line)`, rendered as basics + unparsed `yt-dlp` output line,
             //  or a `ProgressDetail::Parsed { .. }`, rendered as full table of download stats.
             let mut row = Vec::with_capacity(match progress {
-                ProgressDetail::Raw(_) => 4,
-                ProgressDetail::Parsed { .. } => 7,
+                ProgressDetail::Raw(_) => 5,
+                ProgressDetail::Parsed { .. } => 8,
             });
 
+            // Column "#"
+            row.push(index_cell);
+
             // Column "Stage"
-            row.push(Span::styled(
-                match video.stage() {
-                    VideoStage::Initializing => "Intializing...",
-                    VideoStage::Running { .. } => "Running...",
-                    VideoStage::ShuttingDown { .. } => "Shutting down...",
-                    VideoStage::Finished => "Finished!",
-                    VideoStage::Failed => "Failed!",
-                },
-                style::video_stage_style(video.stage()),
-            ));
+            row.push(Span::styled(stage_label, stage_style));
 
             // Column "Progress", using the last known progress,
             // as a fresh value can not in all cases be parsed from the current line.
             row.push(Span::raw(format!("{display_percent:.1} %")));
 
             // Column "Destination"
-            row.push(Span::raw(match video.output_file().as_ref() {
+            row.push(Span::raw(match video.output_file() {
                 Some(output_file) => output_file.as_str(),
+                // Simulated downloads never write a file.
+                None if video.is_simulated() => "(simulated, no file written)",
                 None => "",
             }));
 
@@ -392,7 +1285,12 @@ impl Ui {
                         // Avoid showing the last output line when video progress is entirely finished.
                         // Often this just says "Deleting output file [...]" after merging video
                         // and audio formats. Which is just confusing to end users.
-                        VideoStage::Finished => "",
+                        // Show the completed file size instead, which lines up with column "Size".
+                        VideoStage::Finished => video.final_size().map_or("", String::as_str),
+                        // Show why the downloader skipped the video.
+                        VideoStage::Skipped { reason } | VideoStage::GeoBlocked { reason } => {
+                            reason.as_str()
+                        }
                         // Display the last raw output line as long as video progress is not yet finished.
                         _ => *line,
                     }));
@@ -405,17 +1303,19 @@ impl Ui {
                 }
                 ProgressDetail::Parsed { .. } => {
                     // Columns "Size", "Speed", "ETA" and "Fragments"
-                    row.append(
-                        &mut progress
-                            .to_table_cells()
-                            // Unwrapping is panic-safe here, as `.to_table_cells()`
-                            // always returns `Some([Cow<'a, str>; 4])`
-                            // for the `ProgressDetail::Parsed` enum variant.
-                            .unwrap()
-                            .into_iter()
-                            .map(Span::raw)
-                            .collect::<Vec<Span>>(),
-                    );
+                    // Unwrapping is panic-safe here, as `.to_table_cells()`
+                    // always returns `Some([Cow<'a, str>; 4])`
+                    // for the `ProgressDetail::Parsed` enum variant.
+                    let mut cells = progress.to_table_cells().unwrap();
+
+                    // Finished rows show the completed file size, rather than a stale intermediate one.
+                    if let (VideoStage::Finished, Some(final_size)) =
+                        (video.stage(), video.final_size())
+                    {
+                        cells[0] = Cow::Borrowed(final_size.as_str());
+                    }
+
+                    row.extend(cells.into_iter().map(Span::raw));
 
                     frame.render_widget(
                         Table::new(
@@ -426,8 +1326,22 @@ impl Ui {
                         progress_detail_chunk,
                     );
                 }
-            };
-        };
+            }
+        } else if let VideoStage::Initializing | VideoStage::Queued = video.stage() {
+            // No downloader output yet, e.g. while the title is being fetched or while queued.
+            // Show the animated stage label alone, to signal that the app is not frozen.
+            frame.render_widget(
+                Table::new(
+                    [Row::new([
+                        index_cell,
+                        Span::styled(stage_label, stage_style),
+                    ])],
+                    layout::video_raw_progress_table_layout(),
+                )
+                .column_spacing(2),
+                progress_detail_chunk,
+            );
+        }
     }
 
     fn render_video_progress_bar(
@@ -437,12 +1351,58 @@ impl Ui {
         video: &VideoRead<'_>,
         display_percent: f64,
     ) {
+        let progress_detail = video.progress_detail();
+        let gauge_style = style::gauge_style(
+            video.stage(),
+            progress_detail
+                .as_ref()
+                .and_then(ProgressDetail::parsed_speed_bytes_per_sec),
+        );
+
         let gauge = Gauge::default()
-            .gauge_style(style::gauge_style(video.stage()))
-            .use_unicode(true)
+            .gauge_style(gauge_style)
+            .use_unicode(style::use_unicode())
             .ratio(display_percent / 100.0);
 
-        frame.render_widget(gauge, chunks[chunk_start + 2]);
+        // Do not imply a finished file while formats are still being merged.
+        let gauge = if video.is_merging() {
+            gauge.label(format!("Merging... {display_percent:.0}%"))
+        } else {
+            gauge
+        };
+
+        // Fragment-based downloads, such as HLS, show their downloaded fragments in a secondary gauge,
+        // beside the byte progress, which is often unknown or only estimated.
+        let Some((frag, frag_total)) = progress_detail.as_ref().and_then(ProgressDetail::fragments)
+        else {
+            frame.render_widget(gauge, chunks[chunk_start + 2]);
+            return;
+        };
+
+        let [gauge_area, fragment_gauge_area] =
+            Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)])
+                .spacing(1)
+                .areas(chunks[chunk_start + 2]);
+
+        let fragment_gauge = LineGauge::default()
+            .filled_style(gauge_style)
+            .unfilled_style(style::gauge_unfilled_style())
+            .line_set(style::gauge_line_set())
+            .ratio((f64::from(frag) / f64::from(frag_total)).min(1.0))
+            .label(format!("frag {frag}/{frag_total}"));
+
+        frame.render_widget(gauge, gauge_area);
+        frame.render_widget(fragment_gauge, fragment_gauge_area);
+    }
+
+    // Running downloads whose percentage has not advanced in a while.
+    // Downloads without a percentage, such as livestreams, can not be told apart from stalled ones.
+    fn is_stalled(video: &VideoRead<'_>) -> bool {
+        matches!(video.stage(), VideoStage::Running { .. })
+            && video
+                .percent_done()
+                .is_some_and(|percent_done| percent_done < 100.0)
+            && video.last_progress_at().elapsed() >= STALLED_AFTER
     }
 
     fn video_percent_done_default(stage: &VideoStage) -> f64 {
@@ -455,3 +1415,36 @@ impl Ui {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::Ui;
+    use crate::{
+        session::Config,
+        state::{video::Video, State},
+    };
+
+    #[tokio::test]
+    async fn resumed_bytes_are_not_counted_as_downloaded() {
+        let state = State::new(Config::default());
+        let video = Arc::new(Video::new(
+            "https://player.vimeo.com/video/12345",
+            None::<String>,
+        ));
+        for line in [
+            "[download] Destination: My Video [12345].mp4",
+            "[download] Resuming download at byte 1048576",
+            "[download] 100% of 2.00MiB in 00:00:01 at 1.00MiB/s",
+        ] {
+            video.update_line(&state, line.to_owned()).await;
+        }
+        video.set_stage_finished().await;
+
+        let downloaded_bytes = Ui::downloaded_bytes(&[video.read().await]);
+
+        assert!((downloaded_bytes.finished - 1_048_576.0).abs() < 1.0);
+        assert!(downloaded_bytes.in_progress.abs() < f64::EPSILON);
+    }
+}
@@ -7,19 +7,145 @@ use crate::state::video::VideoRead;
 
 pub(crate) const CHUNKS_PER_VIDEO: usize = 4;
 
-pub(crate) fn layout_chunks(size: Rect, videos: &[VideoRead]) -> Rc<[Rect]> {
+// Below this width, video titles are replaced by the last path segment of the video URL.
+pub(crate) const MIN_TITLE_WIDTH: usize = 24;
+
+const MARGIN: u16 = 1;
+
+// Application title block and table header, with bottom margin
+const APP_FRAME_HEIGHT: u16 = 3;
+
+// Video header block, progress text and progress bar
+const VIDEO_HEIGHT: u16 = 3;
+
+// Summary row of collapsed videos, with bottom margin
+const SUMMARY_HEIGHT: u16 = 1 + style::SPACE_Y;
+
+// Aggregate throughput sparkline, with bottom margin
+const THROUGHPUT_HEIGHT: u16 = 1 + style::SPACE_Y;
+
+// Status line with video counts by stage, at the bottom
+const STATUS_HEIGHT: u16 = 1;
+
+// Minimum terminal height to render the application frame, a single video and the status line.
+pub(crate) const MIN_HEIGHT: u16 = 2 * MARGIN + APP_FRAME_HEIGHT + VIDEO_HEIGHT + STATUS_HEIGHT;
+
+// Optional sections, rendered between the application frame and the videos.
+#[derive(Clone, Copy)]
+pub(crate) struct Sections {
+    pub(crate) has_throughput: bool,
+    pub(crate) has_summary: bool,
+}
+
+// Number of videos which fit into the given area without being clipped.
+pub(crate) fn visible_video_count(area: Rect, video_count: usize, sections: Sections) -> usize {
+    let available_height = area
+        .height
+        .saturating_sub(2 * MARGIN + APP_FRAME_HEIGHT + STATUS_HEIGHT)
+        .saturating_sub(if sections.has_throughput {
+            THROUGHPUT_HEIGHT
+        } else {
+            0
+        })
+        .saturating_sub(if sections.has_summary {
+            SUMMARY_HEIGHT
+        } else {
+            0
+        })
+        // The last video's bottom margin may be cut off.
+        .saturating_add(style::SPACE_Y);
+
+    video_count.min(usize::from(
+        available_height / (VIDEO_HEIGHT + style::SPACE_Y),
+    ))
+}
+
+// Display position of the first rendered video, keeping the selected video, if any, within the page.
+// The page never extends beyond the last video.
+pub(crate) fn scroll_offset(
+    previous_offset: usize,
+    page_len: usize,
+    video_count: usize,
+    maybe_selected_position: Option<usize>,
+) -> usize {
+    let offset = match maybe_selected_position {
+        Some(position) if position < previous_offset => position,
+        Some(position) if position >= previous_offset + page_len => {
+            (position + 1).saturating_sub(page_len)
+        }
+        _ => previous_offset,
+    };
+
+    offset.min(video_count.saturating_sub(page_len))
+}
+
+// Index of the notices chunk, in the remaining space below the videos.
+pub(crate) fn notices_chunk(chunks: &[Rect]) -> usize {
+    chunks.len() - 2
+}
+
+// Index of the status line chunk, which is always the last one.
+pub(crate) fn status_chunk(chunks: &[Rect]) -> usize {
+    chunks.len() - 1
+}
+
+// Index of the throughput sparkline chunk, right below the application frame.
+pub(crate) fn throughput_chunk() -> usize {
+    1
+}
+
+// Index of the summary row chunk, following the throughput sparkline, if any.
+pub(crate) fn summary_chunk(sections: Sections) -> usize {
+    1 + usize::from(sections.has_throughput)
+}
+
+// Index of the first video's first chunk, following the application frame and the optional sections.
+pub(crate) fn first_video_chunk(sections: Sections) -> usize {
+    1 + usize::from(sections.has_throughput) + usize::from(sections.has_summary)
+}
+
+// Overlay centered on the given area, leaving the surrounding application frame visible.
+pub(crate) fn overlay_area(area: Rect) -> Rect {
+    let [_, vertical, _] = Layout::vertical([
+        Constraint::Percentage(15),
+        Constraint::Percentage(70),
+        Constraint::Percentage(15),
+    ])
+    .areas(area);
+
+    let [_, overlay, _] = Layout::horizontal([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(vertical);
+
+    overlay
+}
+
+pub(crate) fn layout_chunks(size: Rect, videos: &[VideoRead], sections: Sections) -> Rc<[Rect]> {
     Layout::default()
         .direction(Direction::Vertical)
-        .margin(1)
-        .constraints(layout_constraints(videos))
+        .margin(MARGIN)
+        .constraints(layout_constraints(videos, sections))
         .split(size)
 }
 
-fn layout_constraints(videos: &[VideoRead]) -> Vec<Constraint> {
-    let mut video_constraints = Vec::with_capacity(1 + videos.len() * 4 + 1); // TODO: Instead of re-allocating, place this vec in Ui struct - and only adjust its length as needed?
+fn layout_constraints(videos: &[VideoRead], sections: Sections) -> Vec<Constraint> {
+    let mut video_constraints = Vec::with_capacity(3 + videos.len() * 4 + 2); // TODO: Instead of re-allocating, place this vec in Ui struct - and only adjust its length as needed?
 
     // Application title block and table header, with bottom margin
-    video_constraints.push(Constraint::Length(3));
+    video_constraints.push(Constraint::Length(APP_FRAME_HEIGHT));
+
+    // Aggregate throughput sparkline
+    if sections.has_throughput {
+        video_constraints.push(Constraint::Length(THROUGHPUT_HEIGHT));
+    }
+
+    // Summary row of collapsed videos
+    if sections.has_summary {
+        video_constraints.push(Constraint::Length(SUMMARY_HEIGHT));
+    }
 
     // Video gauge blocks
     for _ in videos {
@@ -33,13 +159,21 @@ fn layout_constraints(videos: &[VideoRead]) -> Vec<Constraint> {
         video_constraints.push(Constraint::Length(style::SPACE_Y));
     }
 
+    // Notices
     video_constraints.push(Constraint::Min(0));
 
+    // Status line, staying at the bottom
+    video_constraints.push(Constraint::Length(STATUS_HEIGHT));
+
     video_constraints
 }
 
-pub(crate) fn video_raw_progress_table_layout() -> [Constraint; 4] {
+// Column "#", fitting up to 4 digits
+const INDEX_WIDTH: u16 = 4;
+
+pub(crate) fn video_raw_progress_table_layout() -> [Constraint; 5] {
     [
+        Constraint::Length(INDEX_WIDTH),
         Constraint::Percentage(10),
         Constraint::Percentage(10),
         Constraint::Percentage(40),
@@ -47,8 +181,9 @@ pub(crate) fn video_raw_progress_table_layout() -> [Constraint; 4] {
     ]
 }
 
-pub(crate) fn video_progress_detail_table_layout() -> [Constraint; 7] {
+pub(crate) fn video_progress_detail_table_layout() -> [Constraint; 8] {
     [
+        Constraint::Length(INDEX_WIDTH),
         Constraint::Percentage(10),
         Constraint::Percentage(10),
         Constraint::Percentage(40),
@@ -58,3 +193,24 @@ pub(crate) fn video_progress_detail_table_layout() -> [Constraint; 7] {
         Constraint::Percentage(10),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::scroll_offset;
+
+    #[test]
+    fn scrolls_selected_video_into_view() {
+        // Selection below the page scrolls down, just far enough.
+        assert_eq!(scroll_offset(0, 5, 20, Some(7)), 3);
+        // Selection above the page scrolls up.
+        assert_eq!(scroll_offset(10, 5, 20, Some(4)), 4);
+        // Selection within the page keeps the page in place.
+        assert_eq!(scroll_offset(3, 5, 20, Some(6)), 3);
+    }
+
+    #[test]
+    fn page_never_extends_beyond_last_video() {
+        assert_eq!(scroll_offset(18, 5, 20, None), 15);
+        assert_eq!(scroll_offset(3, 5, 4, None), 0);
+    }
+}
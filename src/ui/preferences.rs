@@ -0,0 +1,128 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde_json::{json, Value};
+use tracing::{debug, instrument, warn};
+
+// View preferences, restored on the next launch. Download state is never persisted.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Preferences {
+    pub(crate) collapse_finished: bool,
+    pub(crate) show_throughput: bool,
+}
+
+impl Preferences {
+    // Load the preferences of the previous run, falling back to defaults if there are none.
+    #[instrument]
+    pub(crate) fn load() -> Self {
+        let Some(path) = state_file_path() else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str(&contents) {
+                Ok(value) => Self::from_json(&value),
+                Err(e) => {
+                    warn!("Ignoring malformed UI state file '{}': {e}", path.display());
+                    Self::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("Could not read UI state file '{}': {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    // Persist the preferences for the next run. Failing to do so must not fail the run.
+    #[instrument]
+    pub(crate) fn save(&self) {
+        let Some(path) = state_file_path() else {
+            return;
+        };
+
+        if let Err(e) = self.write(&path) {
+            warn!("{e:#}");
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).wrap_err_with(|| {
+                format!("Could not create UI state directory '{}'", parent.display())
+            })?;
+        }
+
+        debug!("Writing UI state to '{}'...", path.display());
+        fs::write(path, serde_json::to_string_pretty(&self.to_json())?)
+            .wrap_err_with(|| format!("Could not write UI state file '{}'", path.display()))
+    }
+
+    // Unknown or missing keys fall back to their defaults, so that older state files stay readable.
+    fn from_json(value: &Value) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            collapse_finished: value
+                .get("collapse_finished")
+                .and_then(Value::as_bool)
+                .unwrap_or(defaults.collapse_finished),
+            show_throughput: value
+                .get("show_throughput")
+                .and_then(Value::as_bool)
+                .unwrap_or(defaults.show_throughput),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "collapse_finished": self.collapse_finished,
+            "show_throughput": self.show_throughput,
+        })
+    }
+}
+
+// `$XDG_STATE_HOME/showcase-dl/ui.json`, with the XDG default of `~/.local/state`.
+fn state_file_path() -> Option<PathBuf> {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+
+    Some(state_home.join("showcase-dl").join("ui.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Preferences;
+
+    #[test]
+    fn preferences_round_trip_through_json() {
+        let preferences = Preferences {
+            collapse_finished: true,
+            show_throughput: true,
+        };
+
+        assert_eq!(Preferences::from_json(&preferences.to_json()), preferences);
+    }
+
+    #[test]
+    fn unknown_and_missing_keys_fall_back_to_defaults() {
+        assert_eq!(
+            Preferences::from_json(&json!({ "sort": "title" })),
+            Preferences::default()
+        );
+        assert_eq!(
+            Preferences::from_json(&json!({ "collapse_finished": "yes" })),
+            Preferences::default()
+        );
+    }
+}
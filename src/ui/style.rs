@@ -1,35 +1,214 @@
-use ratatui::style::{Color, Modifier, Style};
+use std::env;
 
+use once_cell::sync::OnceCell;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    symbols::{bar, border, line},
+};
+
+use super::theme;
 use crate::state::video::Stage;
 
+static ASCII: OnceCell<bool> = OnceCell::new();
+
 pub(crate) const SPACE_Y: u16 = 1;
 
+// Download speeds below are considered slow, and fast above, respectively.
+const SLOW_SPEED_BYTES_PER_SEC: f64 = 100.0 * 1024.0;
+const FAST_SPEED_BYTES_PER_SEC: f64 = 1024.0 * 1024.0;
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+// Terminals known to lack glyphs for block elements, box drawing or braille patterns.
+const LIMITED_TERMS: [&str; 5] = ["dumb", "linux", "vt100", "vt102", "vt220"];
+
+const ASCII_BORDER_SET: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+const ASCII_LINE_SET: line::Set = line::Set {
+    vertical: "|",
+    horizontal: "=",
+    top_right: "+",
+    top_left: "+",
+    bottom_right: "+",
+    bottom_left: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_down: "+",
+    horizontal_up: "+",
+    cross: "+",
+};
+
+const ASCII_BAR_SET: bar::Set = bar::Set {
+    full: "#",
+    seven_eighths: "#",
+    three_quarters: "=",
+    five_eighths: "=",
+    half: "-",
+    three_eighths: "-",
+    one_quarter: ".",
+    one_eighth: ".",
+    empty: " ",
+};
+
+/// Render with ASCII characters only, if requested or if `$TERM` names a terminal known to lack Unicode glyphs.
+pub(crate) fn init_ascii(ascii: bool) {
+    let is_limited_term = env::var("TERM").is_ok_and(|term| is_limited_term(&term));
+    ASCII.get_or_init(|| ascii || is_limited_term);
+}
+
+fn is_limited_term(term: &str) -> bool {
+    LIMITED_TERMS.contains(&term)
+}
+
+/// Whether widgets may use Unicode glyphs, such as partial block elements in gauges.
+#[inline]
+pub(crate) fn use_unicode() -> bool {
+    !ASCII.get().copied().unwrap_or_default()
+}
+
+/// Select the spinner animation frame for the given render tick.
+#[inline]
+pub(crate) fn spinner_frame(render_tick: usize) -> &'static str {
+    if use_unicode() {
+        SPINNER_FRAMES[render_tick % SPINNER_FRAMES.len()]
+    } else {
+        ASCII_SPINNER_FRAMES[render_tick % ASCII_SPINNER_FRAMES.len()]
+    }
+}
+
+/// Border of the application frame and overlays.
+#[inline]
+pub(crate) fn thick_border_set() -> border::Set {
+    if use_unicode() {
+        border::THICK
+    } else {
+        ASCII_BORDER_SET
+    }
+}
+
+/// Border separating videos.
+#[inline]
+pub(crate) fn plain_border_set() -> border::Set {
+    if use_unicode() {
+        border::PLAIN
+    } else {
+        ASCII_BORDER_SET
+    }
+}
+
+/// Line of the fragment gauge.
+#[inline]
+pub(crate) fn gauge_line_set() -> line::Set {
+    if use_unicode() {
+        line::THICK
+    } else {
+        ASCII_LINE_SET
+    }
+}
+
+/// Unfilled part of the fragment gauge.
+#[inline]
+pub(crate) fn gauge_unfilled_style() -> Style {
+    Style::default().add_modifier(Modifier::DIM)
+}
+
+/// Bars of the throughput sparkline.
+#[inline]
+pub(crate) fn sparkline_bar_set() -> bar::Set {
+    if use_unicode() {
+        bar::NINE_LEVELS
+    } else {
+        ASCII_BAR_SET
+    }
+}
+
+/// Prefix of the stalled stage label.
+#[inline]
+pub(crate) fn warning_symbol() -> &'static str {
+    if use_unicode() {
+        "⚠"
+    } else {
+        "!"
+    }
+}
+
+/// Prefix of the downloader's retry stage label.
+#[inline]
+pub(crate) fn retry_symbol() -> &'static str {
+    if use_unicode() {
+        "↻"
+    } else {
+        "~"
+    }
+}
+
 #[inline]
 pub(crate) fn application_title_style() -> Style {
     Style::default()
-        .fg(Color::White)
+        .fg(theme::palette().text)
         .add_modifier(Modifier::BOLD)
 }
 
 #[inline]
 pub(crate) fn border_style() -> Style {
-    Style::default().fg(Color::LightBlue)
+    Style::default().fg(theme::palette().border)
+}
+
+#[inline]
+pub(crate) fn notice_style() -> Style {
+    Style::default().fg(theme::palette().notice)
+}
+
+#[inline]
+pub(crate) fn status_line_style() -> Style {
+    Style::default().fg(theme::palette().text)
 }
 
 #[inline]
 pub(crate) fn table_header_style() -> Style {
     Style::default()
-        .fg(Color::White)
+        .fg(theme::palette().text)
         .add_modifier(Modifier::BOLD)
 }
 
 #[inline]
 pub(crate) fn video_title_style() -> Style {
     Style::default()
-        .fg(Color::White)
+        .fg(theme::palette().text)
         .add_modifier(Modifier::BOLD)
 }
 
+#[inline]
+pub(crate) fn selected_video_title_style() -> Style {
+    video_title_style().add_modifier(Modifier::REVERSED)
+}
+
+// Cancelled videos are greyed out.
+#[inline]
+pub(crate) fn cancelled_video_title_style() -> Style {
+    Style::default().fg(theme::palette().cancelled)
+}
+
+#[inline]
+pub(crate) fn throughput_style() -> Style {
+    Style::default().fg(theme::palette().speed_fast)
+}
+
+#[inline]
+pub(crate) fn info_style() -> Style {
+    Style::default().fg(theme::palette().text)
+}
+
 #[inline]
 pub(crate) fn video_stage_style(video_stage: &Stage) -> Style {
     Style::default()
@@ -38,18 +217,60 @@ pub(crate) fn video_stage_style(video_stage: &Stage) -> Style {
 }
 
 #[inline]
-pub(crate) fn gauge_style(video_stage: &Stage) -> Style {
+pub(crate) fn stalled_style() -> Style {
     Style::default()
-        .fg(video_stage_color(video_stage))
+        .fg(theme::palette().stalled)
         .add_modifier(Modifier::BOLD)
 }
 
+// Running downloads are colored by throughput, to spot stalled downloads at a glance.
+#[inline]
+pub(crate) fn gauge_style(video_stage: &Stage, speed_bytes_per_sec: Option<f64>) -> Style {
+    let color = match (video_stage, speed_bytes_per_sec) {
+        (Stage::Running { .. }, Some(speed)) => speed_color(speed),
+        _ => video_stage_color(video_stage),
+    };
+
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+fn speed_color(speed_bytes_per_sec: f64) -> Color {
+    let palette = theme::palette();
+
+    if speed_bytes_per_sec < SLOW_SPEED_BYTES_PER_SEC {
+        palette.speed_slow
+    } else if speed_bytes_per_sec < FAST_SPEED_BYTES_PER_SEC {
+        palette.speed_medium
+    } else {
+        palette.speed_fast
+    }
+}
+
 fn video_stage_color(video_stage: &Stage) -> Color {
+    let palette = theme::palette();
+
     match video_stage {
-        Stage::Initializing => Color::LightCyan,
-        Stage::Running { .. } => Color::LightYellow,
-        Stage::ShuttingDown { .. } => Color::LightBlue,
-        Stage::Finished => Color::LightGreen,
-        Stage::Failed => Color::LightRed,
+        Stage::Initializing => palette.initializing,
+        Stage::Queued => palette.queued,
+        Stage::Running { .. } => palette.running,
+        Stage::Paused { .. } => palette.paused,
+        Stage::ShuttingDown => palette.shutting_down,
+        Stage::Finished => palette.finished,
+        Stage::Failed => palette.failed,
+        Stage::Cancelled => palette.cancelled,
+        Stage::Skipped { .. } => palette.skipped,
+        Stage::GeoBlocked { .. } => palette.geo_blocked,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_limited_term;
+
+    #[test]
+    fn detects_limited_terminals() {
+        assert!(is_limited_term("linux"));
+        assert!(is_limited_term("dumb"));
+        assert!(!is_limited_term("xterm-256color"));
     }
 }
@@ -0,0 +1,134 @@
+use std::{borrow::Cow, time::Duration};
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const ELLIPSIS: &str = "…";
+
+/// Truncate `text` to fit into `max_width` terminal columns, appending an ellipsis if truncated.
+/// Truncation respects grapheme cluster boundaries, so that no character is cut in half.
+pub(crate) fn truncate_with_ellipsis(text: &str, max_width: usize) -> Cow<'_, str> {
+    if text.width() <= max_width {
+        return Cow::Borrowed(text);
+    }
+
+    let Some(budget) = max_width.checked_sub(ELLIPSIS.width()) else {
+        return Cow::Borrowed("");
+    };
+
+    let mut width = 0;
+    let mut end = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        width += grapheme.width();
+        if width > budget {
+            break;
+        }
+        end = offset + grapheme.len();
+    }
+
+    Cow::Owned(format!("{}{ELLIPSIS}", &text[..end]))
+}
+
+/// Format a speed in the downloader's binary units, e.g. `1.23MiB/s`.
+pub(crate) fn format_speed(bytes_per_sec: f64) -> String {
+    format!("{}/s", format_bytes(bytes_per_sec))
+}
+
+/// Format a size in the downloader's binary units, e.g. `1.23MiB`.
+pub(crate) fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes;
+    let mut unit = "B";
+    for next_unit in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next_unit;
+    }
+
+    format!("{value:.2}{unit}")
+}
+
+/// Format a duration as `mm:ss`, or as `h:mm:ss` from one hour on.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes:02}:{secs:02}")
+    }
+}
+
+/// The last non-empty path segment of a URL, e.g. the video ID of a player URL.
+pub(crate) fn last_path_segment(url: &str) -> &str {
+    let without_query = url.split(['?', '#']).next().unwrap_or(url);
+    without_query
+        .rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or(without_query)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_bytes, format_duration, last_path_segment, truncate_with_ellipsis};
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_secs(277)), "04:37");
+        assert_eq!(format_duration(Duration::from_secs(3600 + 62)), "1:01:02");
+    }
+
+    #[test]
+    fn truncates_with_ellipsis() {
+        assert_eq!(truncate_with_ellipsis("My Video", 8), "My Video");
+        assert_eq!(truncate_with_ellipsis("My Video", 5), "My V…");
+        assert_eq!(truncate_with_ellipsis("My Video", 1), "…");
+        assert_eq!(truncate_with_ellipsis("My Video", 0), "");
+        assert_eq!(truncate_with_ellipsis("", 0), "");
+    }
+
+    #[test]
+    fn truncates_wide_and_combined_characters_whole() {
+        // Each of these characters takes two columns.
+        assert_eq!(truncate_with_ellipsis("日本語", 6), "日本語");
+        assert_eq!(truncate_with_ellipsis("日本語", 5), "日本…");
+        assert_eq!(truncate_with_ellipsis("日本語", 4), "日…");
+        assert_eq!(truncate_with_ellipsis("日本語", 2), "…");
+
+        // An `e` followed by a combining acute accent is a single grapheme, taking a single column.
+        assert_eq!(
+            truncate_with_ellipsis("e\u{301}e\u{301}e\u{301}", 2),
+            "e\u{301}…"
+        );
+    }
+
+    #[test]
+    fn finds_last_path_segment() {
+        assert_eq!(
+            last_path_segment("https://player.vimeo.com/video/12345"),
+            "12345"
+        );
+        assert_eq!(
+            last_path_segment("https://player.vimeo.com/video/12345/"),
+            "12345"
+        );
+        assert_eq!(
+            last_path_segment("https://player.vimeo.com/video/12345?h=abcdef0123#t=1"),
+            "12345"
+        );
+        assert_eq!(last_path_segment("https://vimeo.com/"), "vimeo.com");
+        assert_eq!(last_path_segment(""), "");
+    }
+
+    #[test]
+    fn formats_bytes_in_binary_units() {
+        assert_eq!(format_bytes(512.0), "512.00B");
+        assert_eq!(format_bytes(1.5 * 1024.0 * 1024.0), "1.50MiB");
+    }
+}
@@ -0,0 +1,119 @@
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use ratatui::style::Color;
+
+static THEME: OnceCell<Theme> = OnceCell::new();
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum Theme {
+    /// Light colors, suited to dark terminal backgrounds
+    #[default]
+    Default,
+    /// No foreground colors, for limited terminals and accessibility
+    Monochrome,
+    /// Saturated colors, for terminals on which light colors are hard to read
+    HighContrast,
+}
+
+// Colors used by the `style::*` functions.
+pub(crate) struct Palette {
+    pub(crate) text: Color,
+    pub(crate) border: Color,
+    pub(crate) notice: Color,
+
+    pub(crate) initializing: Color,
+    pub(crate) queued: Color,
+    pub(crate) running: Color,
+    pub(crate) paused: Color,
+    pub(crate) stalled: Color,
+    pub(crate) shutting_down: Color,
+    pub(crate) finished: Color,
+    pub(crate) failed: Color,
+    pub(crate) cancelled: Color,
+    pub(crate) skipped: Color,
+    pub(crate) geo_blocked: Color,
+
+    pub(crate) speed_slow: Color,
+    pub(crate) speed_medium: Color,
+    pub(crate) speed_fast: Color,
+}
+
+const DEFAULT: Palette = Palette {
+    text: Color::White,
+    border: Color::LightBlue,
+    notice: Color::LightYellow,
+
+    initializing: Color::LightCyan,
+    queued: Color::DarkGray,
+    running: Color::LightYellow,
+    paused: Color::LightMagenta,
+    stalled: Color::LightRed,
+    shutting_down: Color::LightBlue,
+    finished: Color::LightGreen,
+    failed: Color::LightRed,
+    cancelled: Color::DarkGray,
+    skipped: Color::LightBlue,
+    geo_blocked: Color::LightMagenta,
+
+    speed_slow: Color::Red,
+    speed_medium: Color::Yellow,
+    speed_fast: Color::Green,
+};
+
+// `Color::Reset` leaves the terminal's own foreground color in place.
+const MONOCHROME: Palette = Palette {
+    text: Color::Reset,
+    border: Color::Reset,
+    notice: Color::Reset,
+
+    initializing: Color::Reset,
+    queued: Color::Reset,
+    running: Color::Reset,
+    paused: Color::Reset,
+    stalled: Color::Reset,
+    shutting_down: Color::Reset,
+    finished: Color::Reset,
+    failed: Color::Reset,
+    cancelled: Color::Reset,
+    skipped: Color::Reset,
+    geo_blocked: Color::Reset,
+
+    speed_slow: Color::Reset,
+    speed_medium: Color::Reset,
+    speed_fast: Color::Reset,
+};
+
+const HIGH_CONTRAST: Palette = Palette {
+    text: Color::White,
+    border: Color::White,
+    notice: Color::Yellow,
+
+    initializing: Color::Cyan,
+    queued: Color::Gray,
+    running: Color::Yellow,
+    paused: Color::Blue,
+    stalled: Color::Red,
+    shutting_down: Color::Magenta,
+    finished: Color::Green,
+    failed: Color::Red,
+    cancelled: Color::DarkGray,
+    skipped: Color::Cyan,
+    geo_blocked: Color::Magenta,
+
+    speed_slow: Color::Red,
+    speed_medium: Color::Yellow,
+    speed_fast: Color::Green,
+};
+
+// Select the theme once, before rendering. Later calls are ignored.
+pub(crate) fn init(theme: Theme) {
+    THEME.get_or_init(|| theme);
+}
+
+pub(crate) fn palette() -> &'static Palette {
+    match THEME.get().copied().unwrap_or_default() {
+        Theme::Default => &DEFAULT,
+        Theme::Monochrome => &MONOCHROME,
+        Theme::HighContrast => &HIGH_CONTRAST,
+    }
+}
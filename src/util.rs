@@ -1,76 +1,217 @@
-use std::{fmt::Debug, time::Duration};
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
 
 use color_eyre::{
     eyre::{eyre, Result},
     Report,
 };
-use once_cell::sync::OnceCell;
 use reqwest::{
-    header::{HeaderMap, AUTHORIZATION, REFERER, RETRY_AFTER},
-    Client, IntoUrl, Response, StatusCode, Url,
+    header::{
+        HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, REFERER, RETRY_AFTER,
+    },
+    Client, IntoUrl, Method, Response, StatusCode, Url,
 };
-use tokio::task::JoinHandle;
-use tracing::{info, instrument, trace, warn, Instrument};
+use tokio::{task::JoinHandle, time::Instant};
+use tracing::{debug, info, instrument, trace, warn, Instrument};
+
+static CLIENT: Mutex<Option<HttpClient>> = Mutex::new(None);
+
+// An HTTP client, along with the rate-limiting cooldown shared by all of its requests.
+#[derive(Debug, Clone)]
+pub(crate) struct HttpClient {
+    client: Client,
+
+    // Point in time until which no requests are sent, after any request has been rate-limited.
+    // Shared by all requests, so that concurrent requests pause together, rather than each
+    // burning through its retries while the server is throttling.
+    rate_limited_until: Arc<Mutex<Option<Instant>>>,
+}
+
+pub(crate) const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
+
+// Retries of a request failing transiently, e.g. by timing out, before giving up.
+pub(crate) const REQUEST_RETRIES: u8 = 5;
+
+// Delay before the given retry of a request, counting from 1, doubling with every retry.
+pub(crate) fn retry_delay(retry: u8) -> Duration {
+    Duration::from_millis(250) * 2_u32.pow(u32::from(retry.saturating_sub(1)))
+}
+
+// Initialize the shared HTTP client with the given request timeout and additional headers,
+// replacing the client of any previous session.
+// Requests made before use the default timeout and no additional headers.
+pub(crate) fn init_client(http_timeout: Duration, headers: &[(String, String)]) -> Result<()> {
+    let client = build_client(http_timeout, headers)?;
+    *CLIENT.lock().unwrap_or_else(PoisonError::into_inner) = Some(client);
+
+    Ok(())
+}
+
+fn client() -> Result<HttpClient> {
+    let mut client = CLIENT.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(ref client) = *client {
+        return Ok(client.clone());
+    }
+
+    let default_client = build_client(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS), &[])?;
+    *client = Some(default_client.clone());
+
+    Ok(default_client)
+}
+
+fn build_client(http_timeout: Duration, headers: &[(String, String)]) -> Result<HttpClient> {
+    // Sent with every request. Per-request headers, such as the referer, take precedence.
+    let mut default_headers = HeaderMap::new();
+    for (name, value) in headers {
+        default_headers.append(
+            HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| eyre!("invalid header name '{name}'"))?,
+            HeaderValue::from_str(value).map_err(|_| eyre!("invalid value of header '{name}'"))?,
+        );
+    }
 
-static CLIENT: OnceCell<Client> = OnceCell::new();
+    let client = Client::builder()
+        .default_headers(default_headers)
+        .user_agent("Mozilla/5.0 (X11; U; Linux x86_64; en-US; rv:115.0esr) Gecko/20110619 Firefox/115.0esr")
+        // Store cookies, as required to receive a JWT.
+        // See `crate::process::event::get_jwt`.
+        .cookie_store(true)
+        // Do not let a hung endpoint stall the application indefinitely.
+        .connect_timeout(http_timeout)
+        .timeout(http_timeout)
+        .build()?;
 
-// Fetch a URL, applying a referer header
-#[instrument]
+    Ok(HttpClient {
+        client,
+        rate_limited_until: Arc::new(Mutex::new(None)),
+    })
+}
+
+// Fetch a URL with the shared HTTP client, applying a referer header
 pub(crate) async fn fetch_with_retry<U: IntoUrl + Debug>(
     url: U,
     maybe_referer: Option<&str>,
     maybe_authorization: Option<&str>,
 ) -> Result<Response> {
-    let client = CLIENT.get_or_try_init(|| {
-        Client::builder()
-            .user_agent("Mozilla/5.0 (X11; U; Linux x86_64; en-US; rv:115.0esr) Gecko/20110619 Firefox/115.0esr")
-            // Store cookies, as required to receive a JWT.
-            // See `crate::process::event::get_jwt`.
-            .cookie_store(true)
-            .build()
-    })?;
+    fetch_with_retry_using(&client()?, url, maybe_referer, maybe_authorization).await
+}
+
+// Fetch a URL with the given HTTP client, applying a referer header
+#[instrument(skip(client))]
+pub(crate) async fn fetch_with_retry_using<U: IntoUrl + Debug>(
+    client: &HttpClient,
+    url: U,
+    maybe_referer: Option<&str>,
+    maybe_authorization: Option<&str>,
+) -> Result<Response> {
+    let url = url.into_url()?;
+
+    let request_headers = request_headers(maybe_referer, maybe_authorization)?;
+
+    spawn_request_with_retry(client.clone(), Method::GET, url, request_headers, None).await
+}
+
+// POST an URL-encoded form to a URL, retrying like `fetch_with_retry`
+#[instrument(skip(form))]
+pub(crate) async fn post_form_with_retry<U: IntoUrl + Debug>(
+    url: U,
+    maybe_referer: Option<&str>,
+    form: &[(&str, &str)],
+) -> Result<Response> {
+    let client = client()?;
 
     let url = url.into_url()?;
 
-    let request_headers = {
-        let mut header_map = HeaderMap::new();
+    let mut request_headers = request_headers(maybe_referer, None)?;
+    request_headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-www-form-urlencoded"),
+    );
+    // Vimeo answers AJAX-style requests, such as password form submissions, with JSON.
+    request_headers.insert(
+        HeaderName::from_static("x-requested-with"),
+        HeaderValue::from_static("XMLHttpRequest"),
+    );
 
-        if let Some(referer) = maybe_referer
-            .map(TryInto::try_into)
-            .transpose()
-            .map_err(|_| eyre!("invalid `Referer` header value"))?
-        {
-            header_map.insert(REFERER, referer);
-        }
+    let body = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(form)
+        .finish();
 
-        if let Some(authorization_header_value) = maybe_authorization
-            .map(TryInto::try_into)
-            .transpose()
-            .map_err(|_| eyre!("invalid `Authorization` header value"))?
-        {
-            header_map.insert(AUTHORIZATION, authorization_header_value);
-        }
+    spawn_request_with_retry(client, Method::POST, url, request_headers, Some(body)).await
+}
 
-        header_map
-    };
+// POST a JSON body to a URL, retrying like `fetch_with_retry`
+#[instrument(skip(json_body))]
+pub(crate) async fn post_json_with_retry(url: Url, json_body: String) -> Result<Response> {
+    let client = client()?;
+
+    let mut request_headers = HeaderMap::new();
+    request_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
-    spawn_fetch_with_retry(client.clone(), url, request_headers).await
+    spawn_request_with_retry(client, Method::POST, url, request_headers, Some(json_body)).await
 }
 
-#[instrument]
-async fn spawn_fetch_with_retry(
-    client: Client,
+fn request_headers(
+    maybe_referer: Option<&str>,
+    maybe_authorization: Option<&str>,
+) -> Result<HeaderMap> {
+    let mut header_map = HeaderMap::new();
+
+    if let Some(referer) = maybe_referer
+        .map(TryInto::try_into)
+        .transpose()
+        .map_err(|_| eyre!("invalid `Referer` header value"))?
+    {
+        header_map.insert(REFERER, referer);
+    }
+
+    if let Some(authorization_header_value) = maybe_authorization
+        .map(TryInto::try_into)
+        .transpose()
+        .map_err(|_| eyre!("invalid `Authorization` header value"))?
+    {
+        header_map.insert(AUTHORIZATION, authorization_header_value);
+    }
+
+    Ok(header_map)
+}
+
+#[instrument(skip(body))]
+async fn spawn_request_with_retry(
+    client: HttpClient,
+    method: Method,
     url: Url,
     request_headers: HeaderMap,
+    body: Option<String>,
 ) -> Result<Response> {
     tokio::spawn(async move {
-        let mut retries_remaining: u8 = 5;
+        let mut retries_remaining = REQUEST_RETRIES;
         loop {
-            let response = client
-                .get(url.clone())
-                .headers(request_headers.clone())
-                .send()
-                .await?;
+            client.wait_for_rate_limit_cooldown(&url).await;
+
+            let mut request = client
+                .client
+                .request(method.clone(), url.clone())
+                .headers(request_headers.clone());
+            if let Some(ref body) = body {
+                request = request.body(body.clone());
+            }
+            let response = match request.send().await {
+                Ok(response) => response,
+                // Retry on timeout.
+                Err(e) if e.is_timeout() && retries_remaining > 0 => {
+                    retries_remaining -= 1;
+
+                    let delay = retry_delay(REQUEST_RETRIES - retries_remaining);
+                    warn!(%url, ?delay, "Request timed out. Retrying after delay. ({retries_remaining} further retries remaining)");
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                Err(e) => break Err(e.into()),
+            };
             let response_headers = response.headers();
             trace!(?response_headers);
 
@@ -92,13 +233,13 @@ async fn spawn_fetch_with_retry(
                     break Err(eyre!("rate limited throughout all retries"));
                 }
 
-                // Wait, then retry.
-                warn!(%url, wait_seconds, "Received rate-limiting response. Waiting for retry. ({retries_remaining} retries remaining)");
-                tokio::time::sleep(Duration::from_secs(wait_seconds)).await;
+                // Pause all requests, then retry.
+                warn!(%url, wait_seconds, "Received rate-limiting response. Pausing all requests for retry. ({retries_remaining} retries remaining)");
+                client.extend_rate_limit_cooldown(Instant::now() + Duration::from_secs(wait_seconds));
 
                 retries_remaining -= 1;
 
-                info!(%url, wait_seconds, "Retrying now. ({retries_remaining} further retries remaining)");
+                info!(%url, wait_seconds, "Retrying after cooldown. ({retries_remaining} further retries remaining)");
                 continue;
             }
 
@@ -108,6 +249,40 @@ async fn spawn_fetch_with_retry(
     .await?
 }
 
+impl HttpClient {
+    // Wait until the rate-limiting cooldown, if any, has passed.
+    async fn wait_for_rate_limit_cooldown(&self, url: &Url) {
+        let maybe_rate_limited_until = *self
+            .rate_limited_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(rate_limited_until) = maybe_rate_limited_until {
+            if rate_limited_until > Instant::now() {
+                debug!(%url, "Waiting for rate-limiting cooldown.");
+                tokio::time::sleep_until(rate_limited_until).await;
+            }
+        }
+    }
+
+    // Extend the shared rate-limiting cooldown, never shortening a longer cooldown requested by another response.
+    fn extend_rate_limit_cooldown(&self, until: Instant) {
+        let mut rate_limited_until = self
+            .rate_limited_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if rate_limited_until.is_none_or(|rate_limited_until| rate_limited_until < until) {
+            *rate_limited_until = Some(until);
+        }
+    }
+}
+
+// The origin of a URL, in the form used as `Referer` header value, e.g. `https://example.com/`
+pub(crate) fn origin_referer(url: &Url) -> String {
+    format!("{}://{}/", url.scheme(), url.host_str().unwrap_or_default())
+}
+
 // Await the `JoinHandle` if the given `Option` is `Some(_)`
 #[inline]
 pub(crate) async fn maybe_join(maybe_spawned: Option<JoinHandle<Result<()>>>) -> Result<()> {
@@ -117,3 +292,134 @@ pub(crate) async fn maybe_join(maybe_spawned: Option<JoinHandle<Result<()>>>) ->
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::{build_client, fetch_with_retry_using};
+
+    #[tokio::test]
+    async fn rate_limited_requests_are_retried_after_the_requested_delay() {
+        let server = MockServer::start().await;
+        let client = build_client(Duration::from_secs(5), &[]).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/config"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/config"))
+            .and(header("Referer", "https://example.com/page"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let started = tokio::time::Instant::now();
+        let response = fetch_with_retry_using(
+            &client,
+            format!("{}/config", server.uri()),
+            Some("https://example.com/page"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn rate_limiting_throughout_all_retries_fails() {
+        let server = MockServer::start().await;
+        let client = build_client(Duration::from_secs(5), &[]).unwrap();
+
+        // The initial request and five retries.
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .expect(6)
+            .mount(&server)
+            .await;
+
+        let result = fetch_with_retry_using(&client, server.uri(), None, None).await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rate_limiting_pauses_all_requests_of_the_client() {
+        let server = MockServer::start().await;
+        let client = build_client(Duration::from_secs(5), &[]).unwrap();
+        let other_client = build_client(Duration::from_secs(5), &[]).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/limited"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let started = tokio::time::Instant::now();
+        let limited = tokio::spawn({
+            let client = client.clone();
+            let url = format!("{}/limited", server.uri());
+            async move { fetch_with_retry_using(&client, url, None, None).await }
+        });
+        while server.received_requests().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Another client is not paused by the rate-limited one.
+        fetch_with_retry_using(&other_client, format!("{}/other", server.uri()), None, None)
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Any other request of the rate-limited client waits for the cooldown.
+        fetch_with_retry_using(&client, format!("{}/other", server.uri()), None, None)
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+
+        assert_eq!(limited.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn additional_headers_are_sent_with_every_request() {
+        let server = MockServer::start().await;
+        let client = build_client(
+            Duration::from_secs(5),
+            &[(String::from("Origin"), String::from("https://example.com"))],
+        )
+        .unwrap();
+
+        Mock::given(method("GET"))
+            .and(header("Origin", "https://example.com"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = fetch_with_retry_using(&client, server.uri(), None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,24 @@
+use color_eyre::eyre::Result;
+use reqwest::Url;
+use tracing::{info, instrument, warn};
+
+use crate::{report, state::State, util};
+
+// POST a JSON summary of all videos to the given webhook URL.
+// An unreachable webhook endpoint is logged but not an error.
+#[instrument(skip(state))]
+pub(crate) async fn post_summary(webhook_url: Url, state: &State) {
+    if let Err(e) = try_post_summary(webhook_url, state).await {
+        warn!("Failed posting summary to webhook: {e}");
+    }
+}
+
+async fn try_post_summary(webhook_url: Url, state: &State) -> Result<()> {
+    let body = report::summary(state).await.to_string();
+
+    info!("Posting summary to webhook '{webhook_url}'...");
+    let response = util::post_json_with_retry(webhook_url, body).await?;
+    response.error_for_status()?;
+
+    Ok(())
+}